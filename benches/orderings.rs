@@ -0,0 +1,66 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::time::Duration;
+
+use killer_sudoku::data::board::Board;
+use killer_sudoku::solvers::backtracking::{Backtracking, CellOrder, ValueOrder};
+
+/// archive puzzles the node counts are summed over
+const ARCHIVE_PUZZLES: usize = 20;
+
+fn solvers() -> Vec<(String, Backtracking)> {
+    let mut solvers = vec![];
+    for cell_order in CellOrder::ALL {
+        for value_order in ValueOrder::ALL {
+            solvers.push((
+                format!("{} + {}", cell_order.name(), value_order.name()),
+                Backtracking::with_order(cell_order, value_order),
+            ));
+        }
+    }
+    solvers
+}
+
+/// prints the nodes every ordering visits, these don't depend on the machine like the times do
+fn print_node_counts(sample: &Board, archive: &[Board]) {
+    println!("{:<30} {:>12} {:>12}", "ordering", "sample", "archive");
+    for (name, solver) in solvers() {
+        let archive_nodes = archive
+            .iter()
+            .map(|board| solver.count_nodes(board))
+            .sum::<usize>();
+        println!(
+            "{:<30} {:>12} {:>12}",
+            name,
+            solver.count_nodes(sample),
+            archive_nodes
+        );
+    }
+}
+
+fn bench(c: &mut Criterion) {
+    let sample = Board::sample_puzzle();
+    let archive = (0..ARCHIVE_PUZZLES)
+        .map(|i| Board::from_archive(i).unwrap())
+        .collect::<Vec<_>>();
+    print_node_counts(&sample, &archive);
+
+    let mut group = c.benchmark_group("orderings");
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(20));
+    for (name, solver) in solvers() {
+        group.bench_function(format!("sample: {}", name), |b| {
+            b.iter(|| solver.solve(&sample).expect("Solution not found"))
+        });
+        group.bench_function(format!("archive: {}", name), |b| {
+            b.iter(|| {
+                for board in &archive {
+                    solver.solve(board).expect("Solution not found");
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
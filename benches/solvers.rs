@@ -0,0 +1,196 @@
+//! Times every solver over the puzzle corpus, one group per grade so a slow extreme puzzle
+//! doesn't hide behind the easy ones, and the puzzles without or with several solutions.
+//!
+//! Criterion compares each run with the one before. To keep a fixed point to compare against,
+//! save a baseline once and name it later:
+//!
+//! ```text
+//! cargo bench --bench solvers -- --save-baseline main
+//! cargo bench --bench solvers -- --baseline main
+//! ```
+//!
+//! Node counts don't depend on the machine, so they are checked against `benches/nodes.csv`
+//! before anything is timed. Run with `SAVE_NODES=1` to write the current counts instead.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+use killer_sudoku::data::cage_analysis::derived_cages;
+use killer_sudoku::data::corpus::{load_corpus, CorpusPuzzle, Solutions};
+use killer_sudoku::solvers::backtracking::Backtracking;
+use killer_sudoku::solvers::budget::Budget;
+use killer_sudoku::solvers::deduction::{deduce, Candidates, Grade};
+use killer_sudoku::solvers::genetic::Genetic;
+use killer_sudoku::solvers::local_search::LocalSearch;
+use killer_sudoku::solvers::parallel::Parallel;
+use killer_sudoku::solvers::sat::{CageEncoding, Sat};
+
+const NODES_FILE: &str = "./benches/nodes.csv";
+
+/// how much more a node count may grow before it counts as a regression
+const NODE_TOLERANCE: f64 = 1.1;
+
+/// the local solvers can't be relied on to finish, they are timed over this many nodes
+const LOCAL_NODES: usize = 2_000;
+
+/// (solver, puzzle) -> nodes of the solvers that always search the same way
+fn node_counts(corpus: &[CorpusPuzzle]) -> Vec<(String, String, usize)> {
+    let mut counts = vec![];
+    for puzzle in corpus {
+        let board = &puzzle.board;
+        let (_, stats) = Backtracking::new().solve_with_stats(board);
+        counts.push(("backtracking".to_string(), puzzle.name.clone(), stats.nodes));
+        for encoding in CageEncoding::ALL {
+            let (_, stats) = Sat::with_encoding(encoding).solve_with_stats(board);
+            counts.push((
+                format!("sat {}", encoding.name()),
+                puzzle.name.clone(),
+                stats.nodes,
+            ));
+        }
+        let steps = deduce(board).steps.len();
+        counts.push(("deduction".to_string(), puzzle.name.clone(), steps));
+    }
+    counts
+}
+
+/// prints the node counts next to the saved ones and panics if any grew by more than
+/// `NODE_TOLERANCE`
+fn check_node_counts(corpus: &[CorpusPuzzle]) {
+    let counts = node_counts(corpus);
+    if std::env::var_os("SAVE_NODES").is_some() {
+        let mut out = String::from("solver,puzzle,nodes\n");
+        for (solver, puzzle, nodes) in &counts {
+            writeln!(out, "{},{},{}", solver, puzzle, nodes).unwrap();
+        }
+        std::fs::write(NODES_FILE, out).unwrap();
+        println!("saved node counts to {}", NODES_FILE);
+        return;
+    }
+
+    let saved = std::fs::read_to_string(NODES_FILE).unwrap_or_default();
+    let saved = saved
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields = line.split(',').collect::<Vec<_>>();
+            let nodes = fields.get(2)?.parse::<usize>().ok()?;
+            Some(((fields[0].to_string(), fields[1].to_string()), nodes))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut regressions = vec![];
+    println!(
+        "{:<24} {:<14} {:>10} {:>10}",
+        "solver", "puzzle", "saved", "nodes"
+    );
+    for (solver, puzzle, nodes) in counts {
+        let before = saved.get(&(solver.clone(), puzzle.clone())).copied();
+        let before_text = before.map_or("-".to_string(), |n| n.to_string());
+        println!(
+            "{:<24} {:<14} {:>10} {:>10}",
+            solver, puzzle, before_text, nodes
+        );
+        if before.is_some_and(|before| nodes as f64 > before as f64 * NODE_TOLERANCE) {
+            regressions.push(format!(
+                "{} on {}: {} -> {}",
+                solver, puzzle, before_text, nodes
+            ));
+        }
+    }
+    assert!(
+        regressions.is_empty(),
+        "node counts regressed, run with SAVE_NODES=1 if that's expected:\n{}",
+        regressions.join("\n")
+    );
+}
+
+fn bench_group(c: &mut Criterion, name: &str, puzzles: &[&CorpusPuzzle]) {
+    let mut group = c.benchmark_group(format!("solve {}", name));
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(10));
+    let local_budget = Budget::new().with_nodes(LOCAL_NODES);
+    for puzzle in puzzles {
+        let board = &puzzle.board;
+        let label = puzzle.label();
+        group.bench_with_input(
+            BenchmarkId::new("backtracking", &label),
+            board,
+            |b, board| b.iter(|| Backtracking::new().solve(board)),
+        );
+        let parallel = Parallel::new(0);
+        group.bench_with_input(BenchmarkId::new("parallel", &label), board, |b, board| {
+            b.iter(|| parallel.solve(board))
+        });
+        for encoding in CageEncoding::ALL {
+            let id = BenchmarkId::new(format!("sat {}", encoding.name()), &label);
+            group.bench_with_input(id, board, |b, board| {
+                b.iter(|| Sat::with_encoding(encoding).solve(board))
+            });
+        }
+        group.bench_with_input(BenchmarkId::new("deduction", &label), board, |b, board| {
+            b.iter(|| deduce(board))
+        });
+
+        let local_id =
+            |name: &str| BenchmarkId::new(format!("{} ({} nodes)", name, LOCAL_NODES), &label);
+        group.bench_function(local_id("annealing"), |b| {
+            b.iter(|| LocalSearch::annealing(0).solve_within(board, &local_budget))
+        });
+        group.bench_function(local_id("tabu"), |b| {
+            b.iter(|| LocalSearch::tabu(0).solve_within(board, &local_budget))
+        });
+        group.bench_function(local_id("genetic"), |b| {
+            b.iter(|| Genetic::new(0).solve_within(board, &local_budget))
+        });
+    }
+    group.finish();
+}
+
+/// the work done before the first branch, without searching
+fn bench_propagation(c: &mut Criterion, corpus: &[CorpusPuzzle]) {
+    let mut group = c.benchmark_group("propagation");
+    for puzzle in corpus {
+        let board = &puzzle.board;
+        let label = puzzle.label();
+        group.bench_with_input(BenchmarkId::new("candidates", &label), board, |b, board| {
+            b.iter(|| Candidates::new(board))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("derived cages", &label),
+            board,
+            |b, board| b.iter(|| derived_cages(board)),
+        );
+        let derived = derived_cages(board);
+        group.bench_with_input(BenchmarkId::new("next cell", &label), board, |b, board| {
+            b.iter(|| Backtracking::new().next_cell(board, &derived))
+        });
+    }
+    group.finish();
+}
+
+fn bench(c: &mut Criterion) {
+    let corpus = load_corpus();
+    check_node_counts(&corpus);
+
+    for grade in Grade::ALL {
+        let puzzles = corpus
+            .iter()
+            .filter(|p| p.grade == Some(grade))
+            .collect::<Vec<_>>();
+        bench_group(c, grade.name(), &puzzles);
+    }
+    for solutions in [Solutions::None, Solutions::Multiple] {
+        let puzzles = corpus
+            .iter()
+            .filter(|p| p.solutions == solutions)
+            .collect::<Vec<_>>();
+        bench_group(c, &format!("{} solutions", solutions.name()), &puzzles);
+    }
+    bench_propagation(c, &corpus);
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -1,39 +1,101 @@
-extern crate core;
-
-use criterion::{criterion_group, criterion_main, Criterion};
-use rand::prelude::SliceRandom;
-use rand::rngs::ThreadRng;
-use rand::Rng;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use std::time::Duration;
 
-use killer_sudoku::data::board::{Board, Difficulty};
-use killer_sudoku::data::cage::Cage;
+use killer_sudoku::data::board::Board;
+use killer_sudoku::data::cage::Operator;
 use killer_sudoku::data::cage_table::CageTable;
+use killer_sudoku::data::corpus::load_corpus;
+use killer_sudoku::solvers::deduction::Grade;
 
-fn bench(c: &mut Criterion) {
-    let board = Board::sample_puzzle();
+/// the first brute force solvers, on the easy puzzles since they don't pick their cells
+fn bench_brute_force(c: &mut Criterion) {
+    let corpus = load_corpus();
     let mut cage_table = CageTable::from_file("./assets/cage_table.txt");
 
-    let mut group = c.benchmark_group("solver");
-    group.bench_function("brute force", |b| {
-        b.iter(|| {
-            if !simple_brute_force_function(&mut board.clone(), 0) {
-                panic!("Solution not found")
-            }
-        })
+    let mut group = c.benchmark_group("brute force");
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(10));
+    for puzzle in corpus.iter().filter(|p| p.grade == Some(Grade::Easy)) {
+        let board = &puzzle.board;
+        let label = puzzle.label();
+        group.bench_with_input(
+            BenchmarkId::new("brute force", &label),
+            board,
+            |b, board| {
+                b.iter(|| {
+                    if !simple_brute_force_function(&mut board.clone(), 0) {
+                        panic!("Solution not found")
+                    }
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("brute force + backtracking", &label),
+            board,
+            |b, board| {
+                b.iter(|| {
+                    if !brute_force_with_backtracking(&mut board.clone()) {
+                        panic!("Solution not found")
+                    }
+                })
+            },
+        );
+        // the table file only covers 9x9
+        if board.get_size() == 9 {
+            group.bench_with_input(
+                BenchmarkId::new("brute force + backtracking + cage table", &label),
+                board,
+                |b, board| {
+                    b.iter(|| {
+                        if !brute_force_with_backtracking_and_cage_table(
+                            &mut board.clone(),
+                            &mut cage_table,
+                        ) {
+                            panic!("Solution not found")
+                        }
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+/// reading, generating and looking up cage combinations
+fn bench_cage_table(c: &mut Criterion) {
+    let text = std::fs::read_to_string("./assets/cage_table.txt").unwrap();
+
+    let mut group = c.benchmark_group("cage table");
+    group.bench_function("parse", |b| b.iter(|| CageTable::parse(&text).unwrap()));
+    for max_digit in [4, 6, 9, 16] {
+        group.bench_with_input(
+            BenchmarkId::new("generate", max_digit),
+            &max_digit,
+            |b, max_digit| b.iter(|| CageTable::generate(*max_digit)),
+        );
+    }
+    group.bench_function("generate all operators", |b| {
+        b.iter(|| CageTable::generate_all(9))
     });
-    group.bench_function("brute force + backtracking", |b| {
+
+    // every clue of every cage size, most of the lookups of a whole solve
+    let mut sums = CageTable::generate(9);
+    group.bench_function("find every sum", |b| {
         b.iter(|| {
-            if !brute_force_with_backtracking(&mut board.clone()) {
-                panic!("Solution not found")
+            for size in 1..=9 {
+                for sum in 1..=45 {
+                    sums.find(size, sum);
+                }
             }
         })
     });
-
-    group.bench_function("brute force + backtracking + cage table", |b| {
+    let mut all = CageTable::generate_all(9);
+    group.bench_function("find every product", |b| {
         b.iter(|| {
-            if !brute_force_with_backtracking_and_cage_table(&mut board.clone(), &mut cage_table) {
-                panic!("Solution not found")
+            for size in 1..=4 {
+                for clue in 1..=3024 {
+                    all.find_operator(Operator::Product, size, clue);
+                }
             }
         })
     });
@@ -42,18 +104,18 @@ fn bench(c: &mut Criterion) {
 
 /// Iters over all possibilities, unoptimized
 fn simple_brute_force_function(board: &mut Board, i: usize) -> bool {
-    if i >= 81 {
+    let size = board.get_size();
+    if i >= size * size {
         // board.print();
         return board.is_finished();
     }
-    let (x, y) = (i % 9, i / 9);
+    let (x, y) = (i % size, i / size);
 
     if board.get_value(x, y) != 0 {
         return simple_brute_force_function(board, i + 1);
     }
 
-
-    for num in 1..=9 {
+    for num in 1..=size as i32 {
         if board.is_possible(x, y, num) {
             board.unchecked_set_num(x, y, num);
             if simple_brute_force_function(board, i + 1) {
@@ -62,34 +124,26 @@ fn simple_brute_force_function(board: &mut Board, i: usize) -> bool {
         }
         board.unchecked_set_num(x, y, 0); // remove wrong input
     }
-    return false;
+    false
+}
+
+/// the first empty cell, column by column
+fn first_empty(board: &Board) -> Option<(usize, usize)> {
+    let size = board.get_size();
+    (0..size)
+        .flat_map(|x| (0..size).map(move |y| (x, y)))
+        .find(|(x, y)| board.get_value(*x, *y) == 0)
 }
 
 /// brute force with backtracking
 /// finds the first value that's zero, then tries every possibility
 // time O(9^81)
 fn brute_force_with_backtracking(board: &mut Board) -> bool {
-    let (mut x, mut y) = (0, 0);
-    let mut is_empty = true;
-
-    for j in 0..9 {
-        for k in 0..9 {
-            if board.get_value(j, k) == 0 {
-                x = j;
-                y = k;
-
-                is_empty = true;
-                break;
-            }
-        }
-        if !is_empty {
-            break;
-        }
-    }
-    if is_empty {
-        return true;
-    }
-    for num in 1..=9 {
+    let (x, y) = match first_empty(board) {
+        Some(cell) => cell,
+        None => return board.is_finished(),
+    };
+    for num in 1..=board.get_size() {
         if board.is_possible(x, y, num as i32) {
             board.unchecked_set_num(x, y, num as i32);
 
@@ -97,7 +151,7 @@ fn brute_force_with_backtracking(board: &mut Board) -> bool {
                 // board.print();
                 return true;
             } else {
-                board.set_num(x, y, 0);
+                board.unchecked_set_num(x, y, 0);
             }
         }
     }
@@ -105,43 +159,33 @@ fn brute_force_with_backtracking(board: &mut Board) -> bool {
     false
 }
 
-fn brute_force_with_backtracking_and_cage_table(board: &mut Board, cage_table: &mut CageTable) -> bool {
-    let (mut x, mut y) = (0, 0);
-    let mut is_empty = true;
-
-    for j in 0..9 {
-        for k in 0..9 {
-            if board.get_value(j, k) == 0 {
-                x = j;
-                y = k;
-
-                is_empty = true;
-                break;
-            }
-        }
-        if !is_empty {
-            break;
-        }
-    }
-
-    if is_empty {
-        return true;
-    }
+fn brute_force_with_backtracking_and_cage_table(
+    board: &mut Board,
+    cage_table: &mut CageTable,
+) -> bool {
+    let (x, y) = match first_empty(board) {
+        Some(cell) => cell,
+        None => return board.is_finished(),
+    };
 
     let size = board.get_cage_size(x, y) as i32;
     let sum = board.get_sum(x, y);
-    let possibilities = cage_table.find(size, sum).unwrap();
-    for combination in possibilities.combinations {
-        for num in combination {
-            if board.is_possible(x, y, num as i32) {
-                board.unchecked_set_num(x, y, num as i32);
-
-                if brute_force_with_backtracking_and_cage_table(board, cage_table) {
-                    // board.print();
-                    return true;
-                } else {
-                    board.set_num(x, y, 0);
-                }
+    let possibilities = cage_table
+        .find_operator(board.get_operator(x, y), size, sum)
+        .unwrap();
+    // a digit can be in several combinations, only try it once
+    let mut digits = possibilities.combinations.concat();
+    digits.sort_unstable();
+    digits.dedup();
+    for num in digits {
+        if board.is_possible(x, y, num as i32) {
+            board.unchecked_set_num(x, y, num as i32);
+
+            if brute_force_with_backtracking_and_cage_table(board, cage_table) {
+                // board.print();
+                return true;
+            } else {
+                board.unchecked_set_num(x, y, 0);
             }
         }
     }
@@ -149,5 +193,5 @@ fn brute_force_with_backtracking_and_cage_table(board: &mut Board, cage_table: &
     false
 }
 
-criterion_group!(benches, bench);
+criterion_group!(benches, bench_brute_force, bench_cage_table);
 criterion_main!(benches);
@@ -0,0 +1,10 @@
+#![no_main]
+
+use killer_sudoku::data::cage_table::CageTable;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = CageTable::parse(text);
+    }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+
+use killer_sudoku::data::board::Board;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(text) = std::str::from_utf8(data) {
+        if let Ok(board) = Board::parse(text) {
+            // whatever parses has to survive a roundtrip
+            assert_eq!(Board::parse(&board.serialize()), Ok(board));
+        }
+    }
+});
@@ -0,0 +1,464 @@
+use crate::data::board::Board;
+use crate::data::generator::generate;
+use crate::render::{self, pdf, raster, svg, Overlay, RenderOptions};
+use crate::solvers::backtracking::{Backtracking, CellOrder, ValueOrder};
+use crate::solvers::budget::{Budget, SolveOutcome};
+use crate::solvers::genetic::{Crossover, Genetic, Mutation, Selection};
+use crate::solvers::local_search::LocalSearch;
+use crate::solvers::parallel::Parallel;
+use crate::solvers::sat::{self, CageEncoding, Sat};
+use crate::tui;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+const USAGE: &str = "usage:
+  killer-sudoku                       start the game
+  killer-sudoku solve <puzzle> [options]
+  killer-sudoku cnf <puzzle> [--encoding <e>] [--out <file>]   write the puzzle as DIMACS CNF
+  killer-sudoku check <puzzle> [--allow-uncovered]   check the cages of the puzzle
+  killer-sudoku render <puzzle>... [render options]   draw puzzles as SVG, PNG or PDF
+  killer-sudoku tui [<puzzle>]        play in the terminal, the sample puzzle without one
+  killer-sudoku booklet <count> --out <file.pdf> [booklet options]   generate a PDF of new
+                                      puzzles with their solutions at the back
+
+<puzzle> is a puzzle file, `sample` or `archive:<n>`
+
+options:
+  --solver <backtracking|sat|annealing|tabu|genetic>  backtracking by default
+  --encoding <combinations|partial-sums>  how sat encodes cages
+  --cell-order <first|mrv|degree|cage-size|constrained-cage>
+  --value-order <ascending|lcv>
+  --parallel <threads>                search with backtracking on several threads, 0 uses
+                                      one thread per cpu
+  --timeout <seconds>                 give up after this long
+  --max-nodes <n>                     give up after this many nodes
+  --tree <file.dot|file.json>         write the search tree (backtracking without --parallel)
+  --selection <tournament|roulette|rank>  how genetic picks parents
+  --crossover <uniform|one-point>     how genetic combines parents
+  --mutation <swap|shuffle>           how genetic changes children
+  --seed <n>                          random seed of annealing, tabu search and genetic
+  --trace <file.csv>                  write the cost curve of annealing, tabu search or genetic
+  --json                              print the solution and stats as json
+
+render options:
+  --out <file.svg|file.png|file.pdf>  numbered per page if there are several, except PDF,
+                                      SVG on stdout without it
+  --overlay <none|solution|candidates>  what to write in the empty cells
+  --per-page <n>                      lay out n puzzles on each A4 page
+  --cell <pixels>                     cell size when every puzzle gets its own page
+  --scale <factor>                    PNG pixels per SVG pixel, 1 by default
+
+booklet options:
+  --seed <n>                          seed of the first puzzle, the next ones count up from
+                                      it, 0 by default
+  --size <n>                          board size from 4 to 9, 9 by default
+  --per-page <n>                      puzzles on each A4 page, 1 by default";
+
+/// runs the command line, `args` without the program name
+pub fn run(args: &[String]) -> Result<(), String> {
+    match args.split_first() {
+        Some((command, rest)) if command == "solve" => solve(rest),
+        Some((command, rest)) if command == "cnf" => cnf(rest),
+        Some((command, rest)) if command == "check" => check(rest),
+        Some((command, rest)) if command == "render" => render(rest),
+        Some((command, rest)) if command == "booklet" => booklet(rest),
+        Some((command, rest)) if command == "tui" => match rest {
+            [] => tui::run(Board::sample_puzzle()),
+            [puzzle] => tui::run(load_puzzle(puzzle)?),
+            _ => Err(format!("tui takes one puzzle\n\n{}", USAGE)),
+        },
+        Some((command, _)) if command == "help" || command == "--help" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn solve(args: &[String]) -> Result<(), String> {
+    let mut puzzle = None;
+    let mut solver_name = "backtracking".to_string();
+    let mut encoding = CageEncoding::default();
+    let mut cell_order = CellOrder::default();
+    let mut value_order = ValueOrder::default();
+    let mut threads = None;
+    let mut tree_path = None;
+    let mut selection = Selection::default();
+    let mut crossover = Crossover::default();
+    let mut mutation = Mutation::default();
+    let mut seed = 0;
+    let mut trace_path = None;
+    let mut json = false;
+    let mut budget = Budget::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or(format!("{} needs a value\n\n{}", name, USAGE))
+        };
+        match arg.as_str() {
+            "--solver" => solver_name = value(arg)?.clone(),
+            "--encoding" => {
+                let name = value(arg)?;
+                encoding =
+                    CageEncoding::from_name(name).ok_or(format!("unknown encoding {}", name))?;
+            }
+            "--cell-order" => {
+                let name = value(arg)?;
+                cell_order =
+                    CellOrder::from_name(name).ok_or(format!("unknown cell order {}", name))?;
+            }
+            "--value-order" => {
+                let name = value(arg)?;
+                value_order =
+                    ValueOrder::from_name(name).ok_or(format!("unknown value order {}", name))?;
+            }
+            "--parallel" => {
+                let n = value(arg)?;
+                threads = Some(n.parse().map_err(|_| format!("bad thread count {}", n))?);
+            }
+            "--timeout" => {
+                let secs = value(arg)?;
+                let time = secs
+                    .parse()
+                    .ok()
+                    .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                    .ok_or(format!("bad timeout {}", secs))?;
+                budget = budget.with_time(time);
+            }
+            "--max-nodes" => {
+                let n = value(arg)?;
+                budget = budget.with_nodes(n.parse().map_err(|_| format!("bad node count {}", n))?);
+            }
+            "--tree" => tree_path = Some(value(arg)?.clone()),
+            "--selection" => {
+                let name = value(arg)?;
+                selection =
+                    Selection::from_name(name).ok_or(format!("unknown selection {}", name))?;
+            }
+            "--crossover" => {
+                let name = value(arg)?;
+                crossover =
+                    Crossover::from_name(name).ok_or(format!("unknown crossover {}", name))?;
+            }
+            "--mutation" => {
+                let name = value(arg)?;
+                mutation = Mutation::from_name(name).ok_or(format!("unknown mutation {}", name))?;
+            }
+            "--seed" => {
+                let n = value(arg)?;
+                seed = n.parse().map_err(|_| format!("bad seed {}", n))?;
+            }
+            "--trace" => trace_path = Some(value(arg)?.clone()),
+            "--json" => json = true,
+            _ if puzzle.is_none() && !arg.starts_with("--") => puzzle = Some(load_puzzle(arg)?),
+            _ => return Err(format!("unexpected argument {}\n\n{}", arg, USAGE)),
+        }
+    }
+    let board = puzzle.ok_or(format!("no puzzle given\n\n{}", USAGE))?;
+
+    if tree_path.is_some() && (threads.is_some() || solver_name != "backtracking") {
+        return Err("--tree only works with backtracking without --parallel".to_string());
+    }
+    if threads.is_some() && solver_name != "backtracking" {
+        return Err("--parallel only works with backtracking".to_string());
+    }
+    if trace_path.is_some() && !["annealing", "tabu", "genetic"].contains(&solver_name.as_str()) {
+        return Err("--trace only works with annealing, tabu or genetic".to_string());
+    }
+
+    let solver = Backtracking::with_order(cell_order, value_order);
+    let (outcome, stats) = match (solver_name.as_str(), threads) {
+        ("backtracking", Some(threads)) => {
+            Parallel::with_solver(solver.clone(), threads).solve_within(&board, &budget)
+        }
+        ("backtracking", None) => match &tree_path {
+            // recorded during the one budgeted search, so the tree can't outgrow the budget
+            Some(path) => {
+                let (outcome, stats, tree) = solver.solve_recorded_within(&board, &budget);
+                let out = if path.ends_with(".json") {
+                    tree.to_json()
+                } else {
+                    tree.to_dot()
+                };
+                fs::write(path, out).map_err(|e| format!("could not write {}: {}", path, e))?;
+                (outcome, stats)
+            }
+            None => solver.solve_within(&board, &budget),
+        },
+        ("sat", _) => Sat::with_encoding(encoding).solve_within(&board, &budget),
+        ("annealing", _) | ("tabu", _) => {
+            let local = match solver_name.as_str() {
+                "annealing" => LocalSearch::annealing(seed),
+                _ => LocalSearch::tabu(seed),
+            };
+            let (outcome, stats, trace) = local.solve_traced(&board, &budget);
+            if let Some(path) = &trace_path {
+                fs::write(path, trace.to_csv())
+                    .map_err(|e| format!("could not write {}: {}", path, e))?;
+            }
+            (outcome, stats)
+        }
+        ("genetic", _) => {
+            let genetic = Genetic::new(seed)
+                .with_selection(selection)
+                .with_crossover(crossover)
+                .with_mutation(mutation);
+            let (outcome, stats, evolution) = genetic.solve_traced(&board, &budget);
+            if let Some(path) = &trace_path {
+                fs::write(path, evolution.to_csv())
+                    .map_err(|e| format!("could not write {}: {}", path, e))?;
+            }
+            (outcome, stats)
+        }
+        _ => return Err(format!("unknown solver {}\n\n{}", solver_name, USAGE)),
+    };
+
+    if json {
+        let (outcome, solution) = match outcome {
+            SolveOutcome::Solved(solution) => ("solved", Some(solution.get_grid())),
+            SolveOutcome::Unsolvable => ("unsolvable", None),
+            SolveOutcome::GaveUp(_) => ("gave up", None),
+        };
+        let out = serde_json::json!({
+            "outcome": outcome,
+            "solution": solution,
+            "stats": stats,
+        });
+        println!("{}", serde_json::to_string_pretty(&out).unwrap());
+    } else {
+        match outcome {
+            SolveOutcome::Solved(solution) => solution.print(),
+            SolveOutcome::Unsolvable => println!("no solution\n"),
+            SolveOutcome::GaveUp(limit) => println!("gave up ({:?} limit)\n", limit),
+        }
+        println!("{}", stats);
+    }
+    Ok(())
+}
+
+fn cnf(args: &[String]) -> Result<(), String> {
+    let mut puzzle = None;
+    let mut encoding = CageEncoding::default();
+    let mut out = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or(format!("{} needs a value\n\n{}", name, USAGE))
+        };
+        match arg.as_str() {
+            "--encoding" => {
+                let name = value(arg)?;
+                encoding =
+                    CageEncoding::from_name(name).ok_or(format!("unknown encoding {}", name))?;
+            }
+            "--out" => out = Some(value(arg)?.clone()),
+            _ if puzzle.is_none() && !arg.starts_with("--") => puzzle = Some(load_puzzle(arg)?),
+            _ => return Err(format!("unexpected argument {}\n\n{}", arg, USAGE)),
+        }
+    }
+    let board = puzzle.ok_or(format!("no puzzle given\n\n{}", USAGE))?;
+
+    let cnf = sat::encode(&board, encoding);
+    match out {
+        Some(path) => cnf
+            .write(&path)
+            .map_err(|e| format!("could not write {}: {}", path, e)),
+        None => {
+            print!("{}", cnf.to_dimacs());
+            Ok(())
+        }
+    }
+}
+
+fn check(args: &[String]) -> Result<(), String> {
+    let mut puzzle = None;
+    let mut allow_uncovered = false;
+    for arg in args {
+        match arg.as_str() {
+            "--allow-uncovered" => allow_uncovered = true,
+            _ if puzzle.is_none() && !arg.starts_with("--") => puzzle = Some(load_puzzle(arg)?),
+            _ => return Err(format!("unexpected argument {}\n\n{}", arg, USAGE)),
+        }
+    }
+    let board = puzzle.ok_or(format!("no puzzle given\n\n{}", USAGE))?;
+    board.validate_cages(allow_uncovered)?;
+    println!("{} cages, ok", board.get_cages().count());
+    Ok(())
+}
+
+fn render(args: &[String]) -> Result<(), String> {
+    let mut puzzles = vec![];
+    let mut options = RenderOptions::new();
+    let mut out = None;
+    let mut scale = 1.;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or(format!("{} needs a value\n\n{}", name, USAGE))
+        };
+        match arg.as_str() {
+            "--out" => out = Some(value(arg)?.clone()),
+            "--overlay" => {
+                let name = value(arg)?;
+                let overlay =
+                    Overlay::from_name(name).ok_or(format!("unknown overlay {}", name))?;
+                options = options.with_overlay(overlay);
+            }
+            "--per-page" => {
+                let per_page = value(arg)?.parse().map_err(|_| "bad --per-page")?;
+                options = options.with_per_page(per_page);
+            }
+            "--cell" => {
+                let cell = value(arg)?.parse::<f32>().map_err(|_| "bad --cell")?;
+                options = options.with_cell(cell.max(1.));
+            }
+            "--scale" => {
+                scale = value(arg)?.parse::<f32>().map_err(|_| "bad --scale")?;
+                scale = scale.max(0.1);
+            }
+            _ if !arg.starts_with("--") => {
+                let name = Path::new(arg).file_stem().unwrap_or_default();
+                puzzles.push((name.to_string_lossy().to_string(), load_puzzle(arg)?));
+            }
+            _ => return Err(format!("unexpected argument {}\n\n{}", arg, USAGE)),
+        }
+    }
+    if puzzles.is_empty() {
+        return Err(format!("no puzzle given\n\n{}", USAGE));
+    }
+    if options.overlay == Overlay::Solution {
+        for (name, board) in puzzles.iter_mut() {
+            if board.get_solution().iter().flatten().all(|num| *num == 0) {
+                let solution = Backtracking::new()
+                    .solve(board)
+                    .ok_or(format!("{} has no solution", name))?;
+                board.set_solution(&solution);
+            }
+        }
+    }
+
+    let pages = render::render_pages(&puzzles, &options);
+    let out = match out {
+        Some(out) => out,
+        None if pages.len() == 1 => {
+            print!("{}", svg::to_svg(&pages[0]));
+            return Ok(());
+        }
+        None => return Err("several pages need --out".to_string()),
+    };
+    if out.ends_with(".pdf") {
+        let title = puzzles
+            .iter()
+            .map(|(name, _)| &name[..])
+            .collect::<Vec<_>>();
+        return write(&out, pdf::to_pdf(&title.join(", "), &pages));
+    }
+    let png = out.ends_with(".png");
+    for (i, page) in pages.iter().enumerate() {
+        let path = match pages.len() {
+            1 => out.clone(),
+            _ => numbered(&out, i + 1),
+        };
+        let data = match png {
+            true => raster::to_png(page, scale)?,
+            false => svg::to_svg(page).into_bytes(),
+        };
+        write(&path, data)?;
+    }
+    Ok(())
+}
+
+fn booklet(args: &[String]) -> Result<(), String> {
+    let mut count = None;
+    let mut out = None;
+    let mut seed = 0;
+    let mut size = 9;
+    let mut per_page = 1;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or(format!("{} needs a value\n\n{}", name, USAGE))
+        };
+        match arg.as_str() {
+            "--out" => out = Some(value(arg)?.clone()),
+            "--seed" => seed = value(arg)?.parse::<u64>().map_err(|_| "bad --seed")?,
+            "--size" => size = value(arg)?.parse().map_err(|_| "bad --size")?,
+            "--per-page" => per_page = value(arg)?.parse().map_err(|_| "bad --per-page")?,
+            _ if count.is_none() && !arg.starts_with("--") => {
+                count = Some(
+                    arg.parse::<u64>()
+                        .map_err(|_| format!("bad count {}", arg))?,
+                );
+            }
+            _ => return Err(format!("unexpected argument {}\n\n{}", arg, USAGE)),
+        }
+    }
+    let count = count.ok_or(format!("no puzzle count given\n\n{}", USAGE))?;
+    let out = out.ok_or(format!("booklet needs --out\n\n{}", USAGE))?;
+    if count == 0 {
+        return Err("a booklet needs at least one puzzle".to_string());
+    }
+    // larger boards take too long to check for a single solution
+    if !(4..=9).contains(&size) {
+        return Err(format!(
+            "booklets hold puzzles of size 4 to 9, not {}",
+            size
+        ));
+    }
+    let last = seed.checked_add(count - 1).ok_or(format!(
+        "--seed {} is too large for {} puzzles, seeds end at {}",
+        seed,
+        count,
+        u64::MAX
+    ))?;
+
+    let mut puzzles = vec![];
+    for id in 1..=count {
+        let puzzle = generate(size, seed + (id - 1));
+        let name = format!("#{} {}, seed {}", id, puzzle.grade.name(), puzzle.seed);
+        println!("{}", name);
+        puzzles.push((name, puzzle.board));
+    }
+    let options = RenderOptions::new().with_per_page(per_page.max(1));
+    let pages = render::render_booklet(&puzzles, &options);
+    let title = format!(
+        "{} killer sudoku puzzles, seeds {} to {}",
+        count, seed, last
+    );
+    write(&out, pdf::to_pdf(&title, &pages))
+}
+
+fn write(path: &str, data: Vec<u8>) -> Result<(), String> {
+    fs::write(path, data).map_err(|e| format!("could not write {}: {}", path, e))
+}
+
+/// `pages.svg` -> `pages-2.svg`
+fn numbered(path: &str, n: usize) -> String {
+    match path.rsplit_once('.') {
+        Some((stem, extension)) => format!("{}-{}.{}", stem, n, extension),
+        None => format!("{}-{}", path, n),
+    }
+}
+
+fn load_puzzle(name: &str) -> Result<Board, String> {
+    if name == "sample" {
+        return Ok(Board::sample_puzzle());
+    }
+    if let Some(index) = name.strip_prefix("archive:") {
+        let index = index
+            .parse()
+            .map_err(|_| format!("bad archive index {}", index))?;
+        return Board::from_archive(index).ok_or(format!("no archive puzzle {}", index));
+    }
+    let text = fs::read_to_string(name).map_err(|e| format!("could not read {}: {}", name, e))?;
+    Board::parse(&text)
+}
@@ -1,38 +1,121 @@
-use crate::data::cage::{Cage, CageColor};
+use crate::data::cage::{sum_bounds, Cage, CageColor, Operator};
+use crate::data::cage_table::CageTable;
+use crate::data::format;
+use crate::data::variant::{Constraint, UniqueCages, Variant};
 use bevy::prelude::*;
 use rand::Rng;
+use std::collections::BTreeMap;
+
+/// plain 9x9 sudokus with their solutions, one `quizzes,solutions` row each
+const ARCHIVE_PATH: &str = "./assets/sudoku.csv";
+
+/// the largest board `Board::parse` accepts, candidates are kept as bits of a u32
+pub const MAX_SIZE: usize = 25;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Board {
+    size: usize,
+    box_width: usize,
+    box_height: usize,
     grid: Vec<Vec<i32>>,
     solved_grid: Vec<Vec<i32>>,
+    states: Vec<Vec<CellState>>,
     cage_grid: Vec<Vec<Cage>>,
+    /// the cells of every cage by index, in reading order. cells outside cages aren't kept
+    cages: BTreeMap<usize, Vec<(usize, usize)>>,
+    variants: Vec<Variant>,
 }
 
 impl Default for Board {
     fn default() -> Self {
-        Self {
-            grid: vec![vec![0; 9]; 9],
-            solved_grid: vec![vec![0; 9]; 9],
-            cage_grid: vec![vec![Cage::default(); 9]; 9],
-        }
+        Self::new(9)
     }
 }
 
+/// number of puzzles in the archive
+fn archive_len() -> usize {
+    let mut rdr = csv::Reader::from_path(ARCHIVE_PATH).unwrap();
+    rdr.records().count()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Difficulty {
     Easy,
     Normal,
     Hard,
 }
 
+/// who filled a cell
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CellState {
+    Empty,
+    /// handed out with the puzzle, it can't be erased or overwritten
+    Given,
+    /// entered by the player
+    Player,
+    /// filled without the rules being checked, by a solver or the game itself
+    Solver,
+}
+
+/// what came of setting or clearing a cell
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Move {
+    /// the cell was changed
+    Applied,
+    /// the cell is a given of the puzzle
+    Given,
+    /// the cell already holds a digit, clear it first
+    Occupied,
+    /// the digit breaks a rule
+    Conflict,
+    /// there was nothing to clear
+    Empty,
+}
+
 impl Board {
+    /// empty `size` x `size` board, boxes are as square as the size allows (2x3 for 6x6)
+    pub fn new(size: usize) -> Self {
+        let box_height = (1..=size)
+            .filter(|h| size.is_multiple_of(*h) && h * h <= size)
+            .max()
+            .unwrap_or(1);
+        Self::with_boxes(size, size / box_height, box_height)
+    }
+    /// empty board with boxes of `box_width` x `box_height` cells
+    pub fn with_boxes(size: usize, box_width: usize, box_height: usize) -> Self {
+        assert_eq!(
+            box_width * box_height,
+            size,
+            "boxes should hold every digit once"
+        );
+        Self {
+            size,
+            box_width,
+            box_height,
+            grid: vec![vec![0; size]; size],
+            solved_grid: vec![vec![0; size]; size],
+            states: vec![vec![CellState::Empty; size]; size],
+            cage_grid: vec![vec![Cage::default(); size]; size],
+            cages: BTreeMap::new(),
+            variants: vec![],
+        }
+    }
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+    /// (width, height) of a box
+    pub fn get_box_size(&self) -> (usize, usize) {
+        (self.box_width, self.box_height)
+    }
     pub fn get_from_archive() -> Self {
         let mut rnd = rand::thread_rng();
+        Self::from_archive(rnd.gen_range(0..archive_len())).unwrap()
+    }
+    /// puzzle number `index` of the archive, a plain sudoku without cages
+    pub fn from_archive(index: usize) -> Option<Self> {
         let mut board = Board::default();
-        let mut rdr = csv::Reader::from_path("./assets/sudoku.csv").unwrap();
-        let index = rnd.gen_range(0..1000000);
-        let random_board = rdr.records().nth(index).unwrap();
-        let record = random_board.unwrap();
+        let mut rdr = csv::Reader::from_path(ARCHIVE_PATH).unwrap();
+        let record = rdr.records().nth(index)?.unwrap();
 
         let values = &record[0];
         let solution = &record[1];
@@ -49,79 +132,172 @@ impl Board {
             //TODO polyomio's are difficult :-) this probably wouldn't be a valid way
             // board.set_color(x,y,CageColor::from(rnd.gen_range(0..4)));
         }
+        board.mark_givens();
 
-        board
+        Some(board)
     }
     pub fn gen_random(difficulty: Difficulty) -> Self {
-        let mut board = Board::default();
+        Self::gen_random_sized(9, difficulty)
+    }
+    pub fn gen_random_sized(size: usize, difficulty: Difficulty) -> Self {
+        let mut board = Board::new(size);
         let mut rnd = rand::thread_rng();
+        let cells = size * size;
 
+        // given counts for 9x9, scaled to the number of cells
         let amt = match difficulty {
-            Difficulty::Easy => 32,
-            Difficulty::Normal => 24,
-            Difficulty::Hard => 17,
+            Difficulty::Easy => cells * 32 / 81,
+            Difficulty::Normal => cells * 24 / 81,
+            Difficulty::Hard => cells * 17 / 81,
         };
 
         for _ in 0..amt {
-            let mut i = rnd.gen_range(0..81);
-            let (mut x, mut y) = (i % 9, i / 9);
-            let mut num = rnd.gen_range(1..=9);
+            let mut i = rnd.gen_range(0..cells);
+            let (mut x, mut y) = (i % size, i / size);
+            let mut num = rnd.gen_range(1..=size as i32);
             while !board.is_possible(x, y, num) {
-                i = rnd.gen_range(0..81);
-                x = i % 9;
-                y = i / 9;
-                num = rnd.gen_range(1..=9);
+                i = rnd.gen_range(0..cells);
+                x = i % size;
+                y = i / size;
+                num = rnd.gen_range(1..=size as i32);
             }
             board.unchecked_set_num(x, y, num);
         }
+        board.mark_givens();
         board
     }
-    pub fn set_num(&mut self, x: usize, y: usize, num: i32) {
+    /// enters `num` in an empty cell as a player entry, `0` clears it like `clear_cell`
+    pub fn set_num(&mut self, x: usize, y: usize, num: i32) -> Move {
+        if num == 0 {
+            return self.clear_cell(x, y);
+        }
+        if self.is_given(x, y) {
+            return Move::Given;
+        }
+        if self.grid[y][x] != 0 {
+            return Move::Occupied;
+        }
         if !self.is_possible(x, y, num) {
-            return;
+            return Move::Conflict;
         }
 
+        self.enter_num(x, y, num);
+        Move::Applied
+    }
+    /// empties a cell that isn't a given
+    pub fn clear_cell(&mut self, x: usize, y: usize) -> Move {
+        if self.is_given(x, y) {
+            return Move::Given;
+        }
+        if self.grid[y][x] == 0 {
+            return Move::Empty;
+        }
+        self.unchecked_set_num(x, y, 0);
+        Move::Applied
+    }
+    /// clears a cell that isn't a given and returns its digit
+    pub fn take(&mut self, x: usize, y: usize) -> Option<i32> {
+        let num = self.grid[y][x];
+        match self.clear_cell(x, y) {
+            Move::Applied => Some(num),
+            _ => None,
+        }
+    }
+    /// writes a player entry without checking the rules, to restore saved or undone entries
+    pub fn enter_num(&mut self, x: usize, y: usize, num: i32) {
         self.unchecked_set_num(x, y, num);
+        if num != 0 && !self.is_given(x, y) {
+            self.states[y][x] = CellState::Player;
+        }
+    }
+    pub fn get_state(&self, x: usize, y: usize) -> CellState {
+        self.states[y][x]
+    }
+    pub fn is_given(&self, x: usize, y: usize) -> bool {
+        self.states[y][x] == CellState::Given
+    }
+    /// every (x, y) in `state`, row by row
+    pub fn cells_with(&self, state: CellState) -> Vec<(usize, usize)> {
+        let size = self.size;
+        (0..size * size)
+            .map(|i| (i % size, i / size))
+            .filter(|(x, y)| self.states[*y][*x] == state)
+            .collect()
+    }
+    /// makes every filled cell a given, once a puzzle is read, generated or entered by hand
+    pub fn mark_givens(&mut self) {
+        for (states, row) in self.states.iter_mut().zip(&self.grid) {
+            for (state, num) in states.iter_mut().zip(row) {
+                if *num != 0 {
+                    *state = CellState::Given;
+                }
+            }
+        }
+    }
+    /// empties every cell that isn't a given
+    pub fn reset_to_givens(&mut self) {
+        for (x, y) in self.cells_with(CellState::Player) {
+            self.unchecked_set_num(x, y, 0);
+        }
+        for (x, y) in self.cells_with(CellState::Solver) {
+            self.unchecked_set_num(x, y, 0);
+        }
     }
     pub fn is_possible(&self, x: usize, y: usize, num: i32) -> bool {
         let temp = self;
         let temp_grid = &temp.grid;
 
+        if temp_grid[y][x] != 0 {
+            // debug!("square not empty {}", temp_grid[y][x]);
+            return false;
+        }
+        if !self.fits_houses(x, y, num) {
+            return false;
+        }
+
         let cage = &temp.cage_grid[y][x];
         if cage.sum > 0 {
-            let mut cage_indices = vec![];
-            temp.cage_grid.iter().enumerate().for_each(|(y, row)| {
-                row.iter().enumerate().for_each(|(x, c)| {
-                    if c.index == cage.index {
-                        cage_indices.push((x, y));
-                    }
-                })
-            });
-            let current_sum_list = cage_indices
+            let cage_indices = self.get_cage_cells(x, y);
+            let mut digits = cage_indices
                 .iter()
                 .map(|(x, y)| temp_grid[*y][*x])
+                .filter(|n| *n != 0)
                 .collect::<Vec<_>>();
-            let current_sum = current_sum_list.iter().sum::<i32>();
-
-            if temp.get_cage_size_left(x, y) == 1 && num != (cage.sum - current_sum) {
-                return false;
-            }
+            digits.push(num);
 
-            if num + current_sum > cage.sum {
-                // println!("sum would be too big on y {} x {} ", y, x);
+            // every other empty cell of the cage, (x, y) itself is empty
+            let others = cage_indices
+                .iter()
+                .filter(|(cx, cy)| (*cx, *cy) != (x, y) && temp_grid[*cy][*cx] == 0);
+            let reachable = if cage.op == Operator::Sum {
+                let distinct = !self.variants.contains(&Variant::RepeatingCages);
+                let candidates = others
+                    .map(|(cx, cy)| self.get_house_candidates(*cx, *cy, distinct, &digits))
+                    .collect::<Vec<_>>();
+                cage.is_reachable_from(&digits, &candidates, distinct)
+            } else {
+                cage.is_reachable(&digits, others.count())
+            };
+            if !reachable {
+                // println!("cage {} can't reach its clue on y {} x {} ", cage.index, y, x);
                 return false;
             }
         }
 
-        if temp_grid[y][x] != 0 {
-            // debug!("square not empty {}", temp_grid[y][x]);
-            return false;
-        }
-
-        let (square_x, square_y) = (((x) / 3) * 3, ((y) / 3) * 3);
+        self.get_constraints()
+            .iter()
+            .all(|c| c.is_possible(self, x, y, num))
+    }
+    /// whether `num` is still missing from the row, column and box of (x, y)
+    fn fits_houses(&self, x: usize, y: usize, num: i32) -> bool {
+        let temp_grid = &self.grid;
+        let (square_x, square_y) = (
+            (x / self.box_width) * self.box_width,
+            (y / self.box_height) * self.box_height,
+        );
         debug!("square {} {}", square_y, square_x);
-        for y_add in 0..3 {
-            for x_add in 0..3 {
+        for y_add in 0..self.box_height {
+            for x_add in 0..self.box_width {
                 let (new_x, new_y) = (square_x + x_add, square_y + y_add);
                 if temp_grid[new_y][new_x] == num {
                     debug!("Value not possible in square y {} x {}", new_y, new_x);
@@ -130,22 +306,76 @@ impl Board {
             }
         }
 
-        for o in 0..9 {
-            if !(square_y..square_y + 3).contains(&o) && temp_grid[o][x] == num {
+        for (o, row) in temp_grid.iter().enumerate() {
+            if !(square_y..square_y + self.box_height).contains(&o) && row[x] == num {
                 debug!("Value not possible on vertical line");
                 return false; // value not possible
             }
 
-            if !(square_x..square_x + 3).contains(&o) && temp_grid[y][o] == num {
+            if !(square_x..square_x + self.box_width).contains(&o) && temp_grid[y][o] == num {
                 debug!("Value not possible on horizontal line");
                 return false; // value not possible
             }
         }
-
         true
     }
+    /// the digits the empty cell (x, y) could take by its row, column and box alone, leaving
+    /// out `used` when the cage must hold different digits
+    pub(crate) fn get_house_candidates(
+        &self,
+        x: usize,
+        y: usize,
+        distinct: bool,
+        used: &[i32],
+    ) -> Vec<i32> {
+        (1..=self.size as i32)
+            .filter(|n| !(distinct && used.contains(n)) && self.fits_houses(x, y, *n))
+            .collect()
+    }
+    /// the smallest and largest total the empty cells of the cage of (x, y) can still add up
+    /// to, `None` when one of them has no digit left. only rows, columns and boxes are checked,
+    /// so the real range can be narrower
+    pub fn get_cage_bounds(&self, x: usize, y: usize) -> Option<(i32, i32)> {
+        let distinct = !self.variants.contains(&Variant::RepeatingCages);
+        let cells = self.get_cage_cells(x, y);
+        let used = cells
+            .iter()
+            .map(|(cx, cy)| self.grid[*cy][*cx])
+            .filter(|n| *n != 0)
+            .collect::<Vec<_>>();
+        let candidates = cells
+            .iter()
+            .filter(|(cx, cy)| self.grid[*cy][*cx] == 0)
+            .map(|(cx, cy)| self.get_house_candidates(*cx, *cy, distinct, &used))
+            .collect::<Vec<_>>();
+        sum_bounds(&candidates, distinct)
+    }
+    pub fn get_variants(&self) -> &[Variant] {
+        &self.variants
+    }
+    pub fn add_variant(&mut self, variant: Variant) {
+        if !self.variants.contains(&variant) {
+            self.variants.push(variant);
+        }
+    }
+    /// the rules checked on top of rows, columns, boxes and cage sums
+    pub fn get_constraints(&self) -> Vec<&'static dyn Constraint> {
+        let mut constraints: Vec<&'static dyn Constraint> = vec![];
+        if !self.variants.contains(&Variant::RepeatingCages) {
+            constraints.push(&UniqueCages);
+        }
+        constraints.extend(self.variants.iter().filter_map(|v| v.constraint()));
+        constraints
+    }
+    /// writes `num` without checking the rules or givens, filled cells that weren't givens
+    /// count as solver-filled
     pub fn unchecked_set_num(&mut self, x: usize, y: usize, num: i32) {
         self.grid[y][x] = num;
+        self.states[y][x] = match (num, self.states[y][x]) {
+            (0, _) => CellState::Empty,
+            (_, CellState::Given) => CellState::Given,
+            _ => CellState::Solver,
+        };
     }
     pub fn get_value(&self, x: usize, y: usize) -> i32 {
         self.grid[y][x]
@@ -153,6 +383,20 @@ impl Board {
     pub fn get_sum(&self, x: usize, y: usize) -> i32 {
         self.cage_grid[y][x].sum
     }
+    /// index of the cage (x, y) belongs to, 0 when it is not in a cage
+    pub fn get_cage_index(&self, x: usize, y: usize) -> usize {
+        self.cage_grid[y][x].index
+    }
+    pub fn get_operator(&self, x: usize, y: usize) -> Operator {
+        self.cage_grid[y][x].op
+    }
+    /// whether any cage uses something other than a sum, like Calcudoku puzzles do
+    pub fn has_operators(&self) -> bool {
+        self.cage_grid
+            .iter()
+            .flatten()
+            .any(|c| c.index != 0 && c.op != Operator::Sum)
+    }
     pub fn get_color(&self, x: usize, y: usize) -> CageColor {
         match self.cage_grid[y][x].index % 4 {
             0 => CageColor::Yellow,
@@ -163,25 +407,189 @@ impl Board {
         }
     }
     pub fn get_cage_size(&self, x: usize, y: usize) -> usize {
-        let index = self.cage_grid[y][x].index;
-        self.cage_grid
-            .iter()
-            .flat_map(|row| row.iter().filter(|x| x.index == index))
-            .count()
+        match self.cages.get(&self.cage_grid[y][x].index) {
+            Some(cells) => cells.len(),
+            None => self.get_cage_cells(x, y).len(),
+        }
     }
-    pub fn get_cage_size_left(&self, x: usize, y: usize) -> usize {
+    /// (x, y) of every cell in the cage of (x, y), including itself. for a cell outside cages
+    /// that's every other cell outside cages
+    pub fn get_cage_cells(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
         let index = self.cage_grid[y][x].index;
-        let mut cage_positions = vec![];
-        self.cage_grid.iter().enumerate().for_each(|(y, row)| {
-            row.iter().enumerate().for_each(|(x, c)| {
+        if let Some(cells) = self.cages.get(&index) {
+            return cells.clone();
+        }
+        let mut cells = vec![];
+        for (cy, row) in self.cage_grid.iter().enumerate() {
+            for (cx, c) in row.iter().enumerate() {
                 if c.index == index {
-                    cage_positions.push((y, x))
+                    cells.push((cx, cy));
                 }
-            })
-        });
-        cage_positions
+            }
+        }
+        cells
+    }
+    /// every cage index with its cells in reading order, by index
+    pub fn get_cages(&self) -> impl Iterator<Item = (usize, &[(usize, usize)])> {
+        self.cages.iter().map(|(index, cells)| (*index, &cells[..]))
+    }
+    /// moves (x, y) into `cage`, an index of 0 takes it out of its cage
+    pub fn set_cage(&mut self, x: usize, y: usize, cage: Cage) {
+        let old = self.cage_grid[y][x].index;
+        if let Some(cells) = self.cages.get_mut(&old) {
+            cells.retain(|cell| *cell != (x, y));
+            if cells.is_empty() {
+                self.cages.remove(&old);
+            }
+        }
+        self.cage_grid[y][x] = cage;
+        if cage.index != 0 {
+            let cells = self.cages.entry(cage.index).or_default();
+            cells.push((x, y));
+            cells.sort_unstable_by_key(|(x, y)| (*y, *x));
+        }
+    }
+    /// puts `cells` in one cage together with every cage they touch, its clue is the total of
+    /// those cages. returns the index of the cage
+    pub fn merge_cage(&mut self, cells: &[(usize, usize)]) -> usize {
+        let mut merged = cells
+            .iter()
+            .map(|(x, y)| self.cage_grid[*y][*x].index)
+            .filter(|index| *index != 0)
+            .collect::<Vec<_>>();
+        merged.sort_unstable();
+        merged.dedup();
+        let index = match merged.first() {
+            Some(index) => *index,
+            None => self.cages.keys().last().map_or(1, |last| last + 1),
+        };
+
+        let mut members = cells.to_vec();
+        let mut sum = 0;
+        for cells in merged.iter().map(|index| &self.cages[index]) {
+            let (x, y) = cells[0];
+            sum += self.cage_grid[y][x].sum;
+            members.extend(cells);
+        }
+        let cage = Cage {
+            index,
+            sum,
+            op: Operator::Sum,
+        };
+        for (x, y) in members {
+            self.set_cage(x, y, cage);
+        }
+        index
+    }
+    /// sets the clue of cage `index`
+    pub fn set_cage_sum(&mut self, index: usize, sum: i32) {
+        for (x, y) in self.cages.get(&index).cloned().unwrap_or_default() {
+            let cage = self.cage_grid[y][x];
+            self.set_cage(x, y, Cage { sum, ..cage });
+        }
+    }
+    /// takes every cell of cage `index` out of it
+    pub fn remove_cage(&mut self, index: usize) {
+        for (x, y) in self.cages.get(&index).cloned().unwrap_or_default() {
+            self.set_cage(x, y, Cage::default());
+        }
+    }
+    /// the first problem of every cage that has one, see `validate_cages`
+    pub fn cage_problems(&self) -> Vec<(usize, String)> {
+        let distinct = !self.variants.contains(&Variant::RepeatingCages);
+        let mut problems = vec![];
+        for (index, cells) in &self.cages {
+            let (x, y) = cells[0];
+            let cage = self.cage_grid[y][x];
+            let problem = if cells.iter().any(|(x, y)| self.cage_grid[*y][*x] != cage) {
+                format!("cage {} has different clues in its cells", index)
+            } else if distinct && cells.len() > self.size {
+                format!(
+                    "cage {} has {} cells, more than there are digits",
+                    index,
+                    cells.len()
+                )
+            } else if !is_connected(cells) {
+                format!("cage {} is not connected", index)
+            } else if distinct
+                && cage.sum != 0
+                && cage.op != Operator::Unlabelled
+                && CageTable::lookup(self.size as u32, cage.op, cells.len() as i32, cage.sum)
+                    .is_none()
+            {
+                format!(
+                    "no {} distinct digits make {}{} in cage {}",
+                    cells.len(),
+                    cage.sum,
+                    cage.op.label(),
+                    index
+                )
+            } else {
+                continue;
+            };
+            problems.push((*index, problem));
+        }
+        problems
+    }
+    /// Checks the cages make sense, returns the first problem found.
+    ///
+    /// Every cage has to be orthogonally connected, hold one clue in all its cells and have
+    /// at most as many cells as there are digits, a clue distinct digits can produce, unless
+    /// the `RepeatingCages` variant allows repeats. Unless `allow_uncovered`, every cell has to
+    /// be in a cage.
+    pub fn validate_cages(&self, allow_uncovered: bool) -> Result<(), String> {
+        if let Some((_, problem)) = self.cage_problems().into_iter().next() {
+            return Err(problem);
+        }
+        if !allow_uncovered {
+            let size = self.size;
+            let uncovered = (0..size * size)
+                .map(|i| (i % size, i / size))
+                .find(|(x, y)| self.cage_grid[*y][*x].index == 0);
+            if let Some((x, y)) = uncovered {
+                return Err(format!("({}, {}) is not in a cage", x, y));
+            }
+        }
+        Ok(())
+    }
+    /// rebuilds `cages` from `cage_grid`
+    fn index_cages(&mut self) {
+        self.cages.clear();
+        for (y, row) in self.cage_grid.iter().enumerate() {
+            for (x, cage) in row.iter().enumerate().filter(|(_, c)| c.index != 0) {
+                self.cages.entry(cage.index).or_default().push((x, y));
+            }
+        }
+    }
+    /// every other cell that may not hold the same digit as (x, y): its row, column and box,
+    /// plus whatever the constraints add
+    pub fn get_peers(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let (square_x, square_y) = (
+            (x / self.box_width) * self.box_width,
+            (y / self.box_height) * self.box_height,
+        );
+        let mut peers = vec![];
+        for o in 0..self.size {
+            peers.push((o, y));
+            peers.push((x, o));
+        }
+        for y_add in 0..self.box_height {
+            for x_add in 0..self.box_width {
+                peers.push((square_x + x_add, square_y + y_add));
+            }
+        }
+        for constraint in self.get_constraints() {
+            peers.extend(constraint.peers(self, x, y));
+        }
+        peers.sort_unstable();
+        peers.dedup();
+        peers.retain(|p| *p != (x, y));
+        peers
+    }
+    pub fn get_cage_size_left(&self, x: usize, y: usize) -> usize {
+        self.get_cage_cells(x, y)
             .iter()
-            .filter(|(y, x)| self.grid[*y][*x] == 0)
+            .filter(|(x, y)| self.grid[*y][*x] == 0)
             .count()
     }
     pub fn print(&self) {
@@ -195,9 +603,11 @@ impl Board {
         println!();
     }
     pub fn is_finished(&self) -> bool {
-        for o in 0..9 {
-            let mut x_list = vec![1, 2, 3, 4, 5, 6, 7, 8, 9];
-            for x in 0..9 {
+        let digits = (1..=self.size as i32).collect::<Vec<_>>();
+        let boxes_per_row = self.size / self.box_width;
+        for o in 0..self.size {
+            let mut x_list = digits.clone();
+            for x in 0..self.size {
                 let i = self.get_value(x, o);
                 if i == 0 {
                     return false;
@@ -208,8 +618,8 @@ impl Board {
                     return false;
                 }
             }
-            let mut y_list = vec![1, 2, 3, 4, 5, 6, 7, 8, 9];
-            for y in 0..9 {
+            let mut y_list = digits.clone();
+            for y in 0..self.size {
                 let i = self.get_value(o, y);
                 if i == 0 {
                     return false;
@@ -221,10 +631,13 @@ impl Board {
                 }
             }
 
-            let offset = ((o % 3) * 3, (o / 3) * 3);
-            let mut square_list = vec![1, 2, 3, 4, 5, 6, 7, 8, 9];
-            for y in offset.1..offset.1 + 3 {
-                for x in offset.0..offset.0 + 3 {
+            let offset = (
+                (o % boxes_per_row) * self.box_width,
+                (o / boxes_per_row) * self.box_height,
+            );
+            let mut square_list = digits.clone();
+            for y in offset.1..offset.1 + self.box_height {
+                for x in offset.0..offset.0 + self.box_width {
                     let i = self.get_value(x, y);
                     if i == 0 {
                         return false;
@@ -237,7 +650,7 @@ impl Board {
                 }
             }
         }
-        true
+        self.get_constraints().iter().all(|c| c.is_finished(self))
     }
     // pub fn get_row(&self, x:usize) -> &Vec<Square> {
     //     &self.grid[x]
@@ -248,40 +661,324 @@ impl Board {
     pub fn get_grid(self) -> Vec<Vec<i32>> {
         self.grid
     }
+    /// the solution stored with the puzzle, zeros if it has none
+    pub fn get_solution(&self) -> &[Vec<i32>] {
+        &self.solved_grid
+    }
+    /// stores the grid of a solved board as the solution of the puzzle
+    pub fn set_solution(&mut self, solution: &Board) {
+        self.solved_grid = solution.grid.clone();
+    }
+    /// writes the board in the plain-text puzzle format, see `Board::parse`
+    pub fn serialize(&self) -> String {
+        let mut out = format!(
+            "size {} {} {}\n",
+            self.size, self.box_width, self.box_height
+        );
+        format::write_grid(&mut out, "grid", &self.grid);
+        format::write_grid(&mut out, "solution", &self.solved_grid);
+        let cages = self
+            .cage_grid
+            .iter()
+            .map(|row| row.iter().map(|c| c.index as i32).collect())
+            .collect::<Vec<_>>();
+        format::write_grid(&mut out, "cages", &cages);
+
+        let mut sums = self.cage_grid.iter().flatten().collect::<Vec<_>>();
+        sums.sort_by_key(|c| c.index);
+        sums.dedup_by_key(|c| c.index);
+        if !self.variants.is_empty() {
+            let names = self.variants.iter().map(|v| v.name()).collect::<Vec<_>>();
+            out.push_str(&format!("variants {}\n", names.join(" ")));
+        }
+        out.push_str("sums\n");
+        for cage in sums.iter().filter(|c| c.index != 0) {
+            match cage.op {
+                Operator::Sum => out.push_str(&format!("{} {}\n", cage.index, cage.sum)),
+                op => out.push_str(&format!("{} {} {}\n", cage.index, cage.sum, op.symbol())),
+            }
+        }
+        out
+    }
+    /// parses the plain-text puzzle format:
+    /// ```text
+    /// size 9 3 3
+    /// grid
+    /// 0 0 4 3 0 0 2 0 9
+    /// ...
+    /// solution
+    /// 8 6 4 3 7 1 2 5 9
+    /// ...
+    /// cages
+    /// 1 1 2 2 2 3 4 5 6
+    /// ...
+    /// sums
+    /// 1 3
+    /// 2 15
+    /// ...
+    /// ```
+    /// a clue can end in an operator symbol for other cage types, e.g. `4 12 *` for a
+    /// product cage (see `Operator::symbol`), without one it is a sum.
+    /// a puzzle with extra rules lists them on a `variants` line, e.g. `variants diagonal windoku`
+    /// (see `Variant::name`).
+    /// every section is optional, `size` defaults to 9x9 and takes an optional box width and
+    /// height, up to `MAX_SIZE`. cage index 0 means the cell is not part of a cage.
+    /// unknown sections are ignored so other files (like saved games) can embed a puzzle
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let sections = format::sections(text);
+        let mut board = match format::find(&sections, "size") {
+            Some(section) => {
+                let args = section
+                    .args
+                    .iter()
+                    .map(|a| a.parse::<usize>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| "size should be numbers".to_string())?;
+                match args[..] {
+                    [size] if (1..=MAX_SIZE).contains(&size) => Board::new(size),
+                    [size, w, h]
+                        if (1..=MAX_SIZE).contains(&size) && w.checked_mul(h) == Some(size) =>
+                    {
+                        Board::with_boxes(size, w, h)
+                    }
+                    _ => return Err("size should be 'size [box width] [box height]'".to_string()),
+                }
+            }
+            None => Board::default(),
+        };
+        let size = board.size;
+
+        if let Some(section) = format::find(&sections, "variants") {
+            for name in &section.args {
+                let variant =
+                    Variant::from_name(name).ok_or(format!("unknown variant '{}'", name))?;
+                board.add_variant(variant);
+            }
+        }
+        if let Some(section) = format::find(&sections, "grid") {
+            board.grid = format::parse_digits(section, size)?;
+            board.mark_givens();
+        }
+        if let Some(section) = format::find(&sections, "solution") {
+            board.solved_grid = format::parse_digits(section, size)?;
+        }
+        let mut sums = vec![];
+        if let Some(section) = format::find(&sections, "sums") {
+            for line in &section.lines {
+                let words = line.split_whitespace().collect::<Vec<_>>();
+                let (index, sum, op) = match words[..] {
+                    [index, sum] => (index, sum, Operator::Sum),
+                    [index, sum, op] => match Operator::from_symbol(op) {
+                        Some(op) => (index, sum, op),
+                        None => return Err(format!("unknown cage operator '{}'", op)),
+                    },
+                    _ => return Err("sums should contain 'index sum [op]' lines".to_string()),
+                };
+                match (index.parse::<usize>(), sum.parse::<i32>()) {
+                    (Ok(index), Ok(sum)) if index > 0 => sums.push((index, sum, op)),
+                    _ => return Err(format!("invalid cage clue '{}'", line)),
+                }
+            }
+        }
+        if let Some(section) = format::find(&sections, "cages") {
+            let indices = format::parse_grid(section, size)?;
+            for (y, row) in indices.iter().enumerate() {
+                for (x, index) in row.iter().enumerate() {
+                    let index = *index as usize;
+                    if index == 0 {
+                        continue;
+                    }
+                    let (sum, op) = sums
+                        .iter()
+                        .find(|(i, _, _)| *i == index)
+                        .map(|(_, sum, op)| (*sum, *op))
+                        .ok_or(format!("cage {} has no sum", index))?;
+                    board.set_cage(x, y, Cage { index, sum, op });
+                }
+            }
+        }
+        Ok(board)
+    }
+    /// the sample puzzle with the first `rows` rows of its solution given, searches on it stay
+    /// short enough for a debug build
+    #[cfg(test)]
+    pub fn sample_with_rows(rows: usize) -> Self {
+        let mut board = Self::sample_puzzle();
+        let solution = board.get_solution().to_vec();
+        for (y, row) in solution.iter().enumerate().take(rows) {
+            for (x, num) in row.iter().enumerate() {
+                board.unchecked_set_num(x, y, *num);
+            }
+        }
+        board.mark_givens();
+        board
+    }
+    /// how far the sums of the cages are from their clues, added up over every cage
+    #[cfg(test)]
+    pub fn cage_error(&self) -> i32 {
+        self.get_cages()
+            .map(|(_, cells)| {
+                let sum = cells
+                    .iter()
+                    .map(|(x, y)| self.get_value(*x, *y))
+                    .sum::<i32>();
+                (sum - self.get_sum(cells[0].0, cells[0].1)).abs()
+            })
+            .sum()
+    }
     /// hard-coded sample puzzle
     /// source: https://www.dailykillersudoku.com/pdfs/23745.solution.pdf
     /// this puzzle is known to be valid, so it'd be useful for an algorithm
     pub fn sample_puzzle() -> Self {
-        let cage1 = Cage { index: 1, sum: 3 };
-        let cage2 = Cage { index: 2, sum: 15 };
-        let cage3 = Cage { index: 3, sum: 22 };
-        let cage4 = Cage { index: 4, sum: 4 };
-        let cage5 = Cage { index: 5, sum: 16 };
-        let cage6 = Cage { index: 6, sum: 15 };
-        let cage7 = Cage { index: 7, sum: 25 };
-        let cage8 = Cage { index: 8, sum: 17 };
-        let cage9 = Cage { index: 9, sum: 9 };
-        let cage10 = Cage { index: 10, sum: 8 };
-        let cage11 = Cage { index: 11, sum: 20 };
-        let cage12 = Cage { index: 12, sum: 6 };
-        let cage13 = Cage { index: 13, sum: 14 };
-        let cage14 = Cage { index: 14, sum: 17 };
-        let cage15 = Cage { index: 15, sum: 17 };
-        let cage16 = Cage { index: 16, sum: 13 };
-        let cage17 = Cage { index: 17, sum: 20 };
-        let cage18 = Cage { index: 18, sum: 12 };
-        let cage19 = Cage { index: 19, sum: 27 };
-        let cage20 = Cage { index: 20, sum: 6 };
-        let cage21 = Cage { index: 21, sum: 20 };
-        let cage22 = Cage { index: 22, sum: 6 };
-        let cage23 = Cage { index: 23, sum: 10 };
-        let cage24 = Cage { index: 24, sum: 14 };
-        let cage25 = Cage { index: 25, sum: 8 };
-        let cage26 = Cage { index: 26, sum: 16 };
-        let cage27 = Cage { index: 27, sum: 15 };
-        let cage28 = Cage { index: 28, sum: 13 };
-        let cage29 = Cage { index: 29, sum: 17 };
-        Self {
+        let cage1 = Cage {
+            index: 1,
+            sum: 3,
+            op: Operator::Sum,
+        };
+        let cage2 = Cage {
+            index: 2,
+            sum: 15,
+            op: Operator::Sum,
+        };
+        let cage3 = Cage {
+            index: 3,
+            sum: 22,
+            op: Operator::Sum,
+        };
+        let cage4 = Cage {
+            index: 4,
+            sum: 4,
+            op: Operator::Sum,
+        };
+        let cage5 = Cage {
+            index: 5,
+            sum: 16,
+            op: Operator::Sum,
+        };
+        let cage6 = Cage {
+            index: 6,
+            sum: 15,
+            op: Operator::Sum,
+        };
+        let cage7 = Cage {
+            index: 7,
+            sum: 25,
+            op: Operator::Sum,
+        };
+        let cage8 = Cage {
+            index: 8,
+            sum: 17,
+            op: Operator::Sum,
+        };
+        let cage9 = Cage {
+            index: 9,
+            sum: 9,
+            op: Operator::Sum,
+        };
+        let cage10 = Cage {
+            index: 10,
+            sum: 8,
+            op: Operator::Sum,
+        };
+        let cage11 = Cage {
+            index: 11,
+            sum: 20,
+            op: Operator::Sum,
+        };
+        let cage12 = Cage {
+            index: 12,
+            sum: 6,
+            op: Operator::Sum,
+        };
+        let cage13 = Cage {
+            index: 13,
+            sum: 14,
+            op: Operator::Sum,
+        };
+        let cage14 = Cage {
+            index: 14,
+            sum: 17,
+            op: Operator::Sum,
+        };
+        let cage15 = Cage {
+            index: 15,
+            sum: 17,
+            op: Operator::Sum,
+        };
+        let cage16 = Cage {
+            index: 16,
+            sum: 13,
+            op: Operator::Sum,
+        };
+        let cage17 = Cage {
+            index: 17,
+            sum: 20,
+            op: Operator::Sum,
+        };
+        let cage18 = Cage {
+            index: 18,
+            sum: 12,
+            op: Operator::Sum,
+        };
+        let cage19 = Cage {
+            index: 19,
+            sum: 27,
+            op: Operator::Sum,
+        };
+        let cage20 = Cage {
+            index: 20,
+            sum: 6,
+            op: Operator::Sum,
+        };
+        let cage21 = Cage {
+            index: 21,
+            sum: 20,
+            op: Operator::Sum,
+        };
+        let cage22 = Cage {
+            index: 22,
+            sum: 6,
+            op: Operator::Sum,
+        };
+        let cage23 = Cage {
+            index: 23,
+            sum: 10,
+            op: Operator::Sum,
+        };
+        let cage24 = Cage {
+            index: 24,
+            sum: 14,
+            op: Operator::Sum,
+        };
+        let cage25 = Cage {
+            index: 25,
+            sum: 8,
+            op: Operator::Sum,
+        };
+        let cage26 = Cage {
+            index: 26,
+            sum: 16,
+            op: Operator::Sum,
+        };
+        let cage27 = Cage {
+            index: 27,
+            sum: 15,
+            op: Operator::Sum,
+        };
+        let cage28 = Cage {
+            index: 28,
+            sum: 13,
+            op: Operator::Sum,
+        };
+        let cage29 = Cage {
+            index: 29,
+            sum: 17,
+            op: Operator::Sum,
+        };
+        let mut board = Self {
+            size: 9,
+            box_width: 3,
+            box_height: 3,
             grid: vec![vec![0; 9]; 9],
             solved_grid: vec![
                 vec![2, 1, 5, 6, 4, 7, 3, 9, 8],
@@ -294,6 +991,7 @@ impl Board {
                 vec![6, 5, 9, 4, 2, 8, 7, 1, 3],
                 vec![4, 3, 7, 1, 6, 5, 2, 8, 9],
             ],
+            states: vec![vec![CellState::Empty; 9]; 9],
             cage_grid: vec![
                 vec![
                     cage1, cage1, cage2, cage2, cage2, cage3, cage4, cage5, cage6,
@@ -323,13 +1021,36 @@ impl Board {
                     cage19, cage25, cage26, cage23, cage28, cage28, cage28, cage29, cage29,
                 ],
             ],
+            cages: BTreeMap::new(),
+            variants: vec![],
+        };
+        board.index_cages();
+        board
+    }
+}
+
+/// whether every cell of `cells` can be reached from the first one through orthogonal
+/// neighbours in `cells`
+fn is_connected(cells: &[(usize, usize)]) -> bool {
+    let mut seen = vec![cells[0]];
+    let mut next = vec![cells[0]];
+    while let Some((x, y)) = next.pop() {
+        for cell in cells {
+            if !seen.contains(cell) && x.abs_diff(cell.0) + y.abs_diff(cell.1) == 1 {
+                seen.push(*cell);
+                next.push(*cell);
+            }
         }
     }
+    seen.len() == cells.len()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::data::board::Board;
+    use crate::data::board::{Board, CellState, Move};
+    use crate::data::cage::{Cage, Operator};
+    use crate::data::cage_table::CageTable;
+    use crate::data::variant::Variant;
 
     #[test]
     fn check_is_finished() {
@@ -368,4 +1089,221 @@ mod tests {
         assert!(!board.is_possible(1, 0, 9));
         board.unchecked_set_num(1, 0, 1);
     }
+
+    #[test]
+    fn check_moves() {
+        let mut board = Board::parse("size 4\ngrid\n1 0 0 0\n0 0 0 0\n0 0 0 0\n0 0 0 0").unwrap();
+        assert!(board.is_given(0, 0));
+        assert_eq!(board.clear_cell(0, 0), Move::Given);
+        assert_eq!(board.set_num(0, 0, 0), Move::Given);
+        assert_eq!(board.take(0, 0), None);
+        assert_eq!(board.get_value(0, 0), 1);
+
+        assert_eq!(board.set_num(1, 0, 1), Move::Conflict);
+        assert_eq!(board.set_num(1, 0, 2), Move::Applied);
+        assert_eq!(board.set_num(1, 0, 3), Move::Occupied);
+        assert!(!board.is_given(1, 0));
+        assert_eq!(board.take(1, 0), Some(2));
+        assert_eq!(board.clear_cell(1, 0), Move::Empty);
+        assert_eq!(board.set_num(1, 0, 3), Move::Applied);
+        assert_eq!(board.set_num(1, 0, 0), Move::Applied);
+        assert_eq!(board.get_value(1, 0), 0);
+    }
+
+    #[test]
+    fn check_cell_states() {
+        let mut board = Board::from_archive(0).unwrap();
+        let givens = board.cells_with(CellState::Given);
+        assert!(!givens.is_empty());
+        assert!(givens.iter().all(|(x, y)| board.get_value(*x, *y) != 0));
+
+        let (x, y) = board.cells_with(CellState::Empty)[0];
+        let num = board.get_solution()[y][x];
+        assert_eq!(board.set_num(x, y, num), Move::Applied);
+        assert_eq!(board.get_state(x, y), CellState::Player);
+        let (x, y) = board.cells_with(CellState::Empty)[0];
+        board.unchecked_set_num(x, y, board.get_solution()[y][x]);
+        assert_eq!(board.get_state(x, y), CellState::Solver);
+
+        board.reset_to_givens();
+        assert_eq!(board.cells_with(CellState::Given), givens);
+        assert!(board.cells_with(CellState::Player).is_empty());
+        assert!(board.cells_with(CellState::Solver).is_empty());
+        assert_eq!(board, Board::from_archive(0).unwrap());
+    }
+
+    #[test]
+    fn check_validate_cages() {
+        let mut board = Board::sample_puzzle();
+        assert_eq!(board.validate_cages(false), Ok(()));
+        assert_eq!(board.get_cages().count(), 29);
+        assert_eq!(board.get_cage_cells(0, 0), vec![(0, 0), (1, 0)]);
+
+        let lone = Cage {
+            index: 30,
+            sum: 5,
+            op: Operator::Sum,
+        };
+        board.set_cage(0, 0, lone);
+        assert_eq!(board.get_cage_cells(1, 0), vec![(1, 0)]);
+        assert_eq!(board.validate_cages(false), Ok(()));
+
+        let mut board = Board::new(4);
+        let two = Cage {
+            index: 1,
+            sum: 3,
+            op: Operator::Sum,
+        };
+        board.set_cage(0, 0, two);
+        board.set_cage(2, 0, two);
+        assert_eq!(
+            board.validate_cages(true),
+            Err("cage 1 is not connected".to_string())
+        );
+        board.set_cage(2, 0, Cage::default());
+        board.set_cage(1, 0, Cage { sum: 2, ..two });
+        assert_eq!(
+            board.validate_cages(true),
+            Err("cage 1 has different clues in its cells".to_string())
+        );
+        board.set_cage(0, 0, Cage { sum: 2, ..two });
+        assert!(board.validate_cages(true).is_err());
+        board.add_variant(Variant::RepeatingCages);
+        assert_eq!(board.validate_cages(true), Ok(()));
+        assert!(board.validate_cages(false).is_err());
+    }
+
+    #[test]
+    fn check_merge_cages() {
+        let mut board = Board::new(4);
+        let first = board.merge_cage(&[(0, 0), (1, 0)]);
+        board.set_cage_sum(first, 3);
+        let second = board.merge_cage(&[(2, 0)]);
+        board.set_cage_sum(second, 7);
+        assert_eq!((first, second), (1, 2));
+
+        assert_eq!(board.merge_cage(&[(1, 1), (2, 0)]), 2);
+        assert_eq!(board.get_cage_cells(2, 0), vec![(2, 0), (1, 1)]);
+        assert_eq!(board.merge_cage(&[(0, 0), (1, 1)]), 1);
+        assert_eq!(board.get_cages().count(), 1);
+        assert_eq!(board.get_sum(2, 0), 10);
+        assert_eq!(board.cage_problems(), vec![]);
+
+        board.set_cage_sum(1, 50);
+        assert_eq!(board.cage_problems().len(), 1);
+        board.remove_cage(1);
+        assert_eq!(board.get_cages().count(), 0);
+        assert_eq!(board, Board::new(4));
+    }
+
+    #[test]
+    fn check_cage_bounds() {
+        // a 7 in two cells of a 4x4 board needs 3 + 4
+        let mut board = Board::new(4);
+        let cage = Cage {
+            index: 1,
+            sum: 7,
+            op: Operator::Sum,
+        };
+        board.set_cage(0, 0, cage);
+        board.set_cage(1, 0, cage);
+        assert_eq!(board.get_cage_bounds(0, 0), Some((3, 7)));
+        assert!(!board.is_possible(0, 0, 1));
+        assert!(!board.is_possible(0, 0, 2));
+        assert!(board.is_possible(0, 0, 3));
+
+        // with 4 used in the other cell's column only 3 + 4 in this order is left
+        board.unchecked_set_num(1, 2, 4);
+        assert!(!board.is_possible(0, 0, 3));
+        assert!(board.is_possible(0, 0, 4));
+    }
+
+    #[test]
+    fn check_sizes() {
+        let mut board = Board::new(6);
+        assert_eq!(board.get_box_size(), (3, 2));
+        board.grid = vec![
+            vec![1, 2, 3, 4, 5, 6],
+            vec![4, 5, 6, 1, 2, 3],
+            vec![2, 3, 1, 5, 6, 4],
+            vec![5, 6, 4, 2, 3, 1],
+            vec![3, 1, 2, 6, 4, 5],
+            vec![6, 4, 5, 3, 1, 2],
+        ];
+        assert!(board.is_finished());
+        board.mark_givens();
+        assert_eq!(Board::parse(&board.serialize()), Ok(board.clone()));
+
+        assert_eq!(board.clear_cell(5, 5), Move::Given);
+        board.unchecked_set_num(5, 5, 0);
+        assert!(!board.is_possible(5, 5, 1));
+        assert!(board.is_possible(5, 5, 2));
+        assert_eq!(Board::new(16).get_box_size(), (4, 4));
+    }
+
+    #[test]
+    fn check_variants() {
+        let mut board = Board::default();
+        board.unchecked_set_num(4, 4, 5);
+        assert!(board.is_possible(0, 0, 5));
+        assert!(board.is_possible(5, 6, 5));
+
+        board.add_variant(Variant::Diagonal);
+        assert!(!board.is_possible(0, 0, 5));
+        assert!(!board.is_possible(8, 0, 5));
+        board.add_variant(Variant::AntiKnight);
+        assert!(!board.is_possible(5, 6, 5));
+        assert!(board.is_possible(1, 2, 5));
+        board.mark_givens();
+        assert_eq!(Board::parse(&board.serialize()), Ok(board));
+
+        let mut board = Board::sample_puzzle();
+        board.unchecked_set_num(2, 2, 4);
+        assert!(!board.is_possible(3, 3, 4));
+        board.add_variant(Variant::RepeatingCages);
+        assert!(board.is_possible(3, 3, 4));
+    }
+
+    #[test]
+    fn check_operators() {
+        let mut board = Board::with_boxes(4, 4, 1);
+        let product = Cage {
+            index: 1,
+            sum: 12,
+            op: Operator::Product,
+        };
+        let difference = Cage {
+            index: 2,
+            sum: 3,
+            op: Operator::Difference,
+        };
+        board.set_cage(0, 0, product);
+        board.set_cage(1, 0, product);
+        board.set_cage(0, 1, difference);
+        board.set_cage(0, 2, difference);
+
+        board.unchecked_set_num(0, 0, 3);
+        assert!(board.is_possible(1, 0, 4));
+        assert!(!board.is_possible(1, 0, 2));
+        board.unchecked_set_num(0, 1, 1);
+        assert!(board.is_possible(0, 2, 4));
+        assert!(!board.is_possible(0, 2, 2));
+        board.mark_givens();
+        assert_eq!(Board::parse(&board.serialize()), Ok(board));
+
+        let mut table = CageTable::generate_all(4);
+        let item = table.find_operator(Operator::Product, 2, 12).unwrap();
+        assert_eq!(item.combinations, vec![vec![3, 4]]);
+        assert_eq!(Operator::Quotient.evaluate(&[2, 4]), Some(2));
+        assert_eq!(Operator::Quotient.evaluate(&[3, 4]), None);
+    }
+
+    #[test]
+    fn check_parse_serialize() {
+        let mut board = Board::sample_puzzle();
+        board.unchecked_set_num(4, 2, 8);
+        board.mark_givens();
+        assert_eq!(Board::parse(&board.serialize()), Ok(board));
+        assert!(Board::parse("grid\n1 2 3").is_err());
+    }
 }
@@ -1,7 +1,149 @@
 #[derive(Default, Debug, Copy, Clone, PartialEq)]
 pub struct Cage {
     pub(crate) index: usize,
+    /// the clue of the cage, which is a sum unless `op` says otherwise
     pub(crate) sum: i32,
+    pub(crate) op: Operator,
+}
+
+/// How the digits of a cage combine into its clue.
+///
+/// Killer sudoku only uses `Sum`, the others allow Calcudoku/KenKen style puzzles. Those have
+/// no boxes, which a board with `Board::with_boxes(size, size, 1)` gets close to.
+#[derive(Default, Debug, Copy, Clone, PartialEq)]
+pub enum Operator {
+    #[default]
+    Sum,
+    Product,
+    /// the largest digit minus the others, for two cells the absolute difference
+    Difference,
+    /// the largest digit divided by the others, only whole results count
+    Quotient,
+    /// a cage without a clue, its digits only have to differ
+    Unlabelled,
+}
+
+impl Operator {
+    pub const ALL: [Operator; 5] = [
+        Operator::Sum,
+        Operator::Product,
+        Operator::Difference,
+        Operator::Quotient,
+        Operator::Unlabelled,
+    ];
+
+    /// symbol used in the puzzle format
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Operator::Sum => "+",
+            Operator::Product => "*",
+            Operator::Difference => "-",
+            Operator::Quotient => "/",
+            Operator::Unlabelled => "?",
+        }
+    }
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        Self::ALL.iter().find(|op| op.symbol() == symbol).copied()
+    }
+    /// symbol shown next to the clue
+    pub fn label(&self) -> &'static str {
+        match self {
+            Operator::Sum => "+",
+            Operator::Product => "×",
+            Operator::Difference => "−",
+            Operator::Quotient => "÷",
+            Operator::Unlabelled => "",
+        }
+    }
+    /// the clue a completely filled cage produces, `None` if it produces none
+    pub fn evaluate(&self, digits: &[i32]) -> Option<i32> {
+        let mut sorted = digits.to_vec();
+        sorted.sort_unstable_by(|a, b| b.cmp(a));
+        let (max, rest) = sorted.split_first()?;
+        match self {
+            Operator::Sum => Some(digits.iter().sum()),
+            Operator::Product => product(digits),
+            Operator::Difference => Some(max - rest.iter().sum::<i32>()),
+            Operator::Quotient => {
+                // a divisor too large for an i32 is larger than `max` too
+                let divisor = product(rest)?;
+                (max % divisor == 0).then(|| max / divisor)
+            }
+            Operator::Unlabelled => None,
+        }
+    }
+}
+
+/// the product of `digits`, `None` when it doesn't fit an i32, which happens from 13 digits of
+/// 16x16 boards on
+fn product(digits: &[i32]) -> Option<i32> {
+    digits
+        .iter()
+        .try_fold(1i32, |product, d| product.checked_mul(*d))
+}
+
+impl Cage {
+    /// whether a cage holding `digits` with `left` cells still empty can end up at its clue
+    pub fn is_reachable(&self, digits: &[i32], left: usize) -> bool {
+        if left == 0 {
+            return self.op == Operator::Unlabelled || self.op.evaluate(digits) == Some(self.sum);
+        }
+        match self.op {
+            Operator::Sum => digits.iter().sum::<i32>() <= self.sum,
+            Operator::Product => product(digits).is_some_and(|p| self.sum % p == 0),
+            // the largest digit might still be missing, nothing to check yet
+            Operator::Difference | Operator::Quotient | Operator::Unlabelled => true,
+        }
+    }
+    /// like `is_reachable`, but a sum cage also checks that its empty cells, which can take
+    /// `candidates`, still add up to what is missing, neither too little nor too much
+    pub fn is_reachable_from(
+        &self,
+        digits: &[i32],
+        candidates: &[Vec<i32>],
+        distinct: bool,
+    ) -> bool {
+        if !self.is_reachable(digits, candidates.len()) {
+            return false;
+        }
+        if self.op != Operator::Sum || candidates.is_empty() {
+            return true;
+        }
+        let missing = self.sum - digits.iter().sum::<i32>();
+        match sum_bounds(candidates, distinct) {
+            Some((min, max)) => min <= missing && missing <= max,
+            None => false,
+        }
+    }
+}
+
+/// the smallest and largest total of cells that each take one of their `candidates`, `None` if
+/// some cell has none. with `distinct` the cells also need different digits, so at best they
+/// take the smallest (or largest) digits any of them has
+pub fn sum_bounds(candidates: &[Vec<i32>], distinct: bool) -> Option<(i32, i32)> {
+    if candidates.iter().any(|c| c.is_empty()) {
+        return None;
+    }
+    let mut min = candidates
+        .iter()
+        .map(|c| c.iter().min().unwrap())
+        .sum::<i32>();
+    let mut max = candidates
+        .iter()
+        .map(|c| c.iter().max().unwrap())
+        .sum::<i32>();
+    if distinct {
+        let mut pool = candidates.concat();
+        pool.sort_unstable();
+        pool.dedup();
+        let n = candidates.len();
+        if pool.len() < n {
+            return None;
+        }
+        min = min.max(pool[..n].iter().sum());
+        max = max.min(pool[pool.len() - n..].iter().sum());
+    }
+    Some((min, max))
 }
 
 #[derive(Debug, Clone, PartialEq)]
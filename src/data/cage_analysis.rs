@@ -0,0 +1,205 @@
+use crate::data::board::Board;
+use crate::data::cage::{Cage, Operator};
+use crate::data::variant::Variant;
+
+/// Cells whose digits must add up to `sum` without being a cage of the puzzle.
+///
+/// Found by taking the cages that lie completely inside a region with a known total out of it,
+/// what is left of the region has to make up the difference ("cage splitting"). A row with
+/// cages of 10 and 17 inside it leaves its other cells at 45 - 27 = 18.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DerivedCage {
+    /// sorted (x, y) of the cells
+    pub cells: Vec<(usize, usize)>,
+    pub sum: i32,
+    /// whether the digits must differ, true unless it came out of a repeating cage
+    pub distinct: bool,
+    /// the region the cage was cut out of
+    pub source: Region,
+}
+
+/// A group of cells with a known total.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Region {
+    Row(usize),
+    Column(usize),
+    /// boxes are numbered row by row
+    Box(usize),
+    /// the cage with this index
+    Cage(usize),
+    /// the derived cage at this position of the list
+    Derived(usize),
+}
+
+impl DerivedCage {
+    /// whether the cage can still reach its sum with `num` in its empty cell (x, y)
+    pub fn allows(&self, board: &Board, x: usize, y: usize, num: i32) -> bool {
+        let mut digits = self
+            .cells
+            .iter()
+            .map(|(cx, cy)| board.get_value(*cx, *cy))
+            .filter(|n| *n != 0)
+            .collect::<Vec<_>>();
+        digits.push(num);
+        let candidates = self
+            .cells
+            .iter()
+            .filter(|(cx, cy)| (*cx, *cy) != (x, y) && board.get_value(*cx, *cy) == 0)
+            .map(|(cx, cy)| board.get_house_candidates(*cx, *cy, self.distinct, &digits))
+            .collect::<Vec<_>>();
+        let cage = Cage {
+            index: 0,
+            sum: self.sum,
+            op: Operator::Sum,
+        };
+        cage.is_reachable_from(&digits, &candidates, self.distinct)
+    }
+
+    /// the empty cells of the cage on `board`
+    pub fn empty_cells(&self, board: &Board) -> Vec<(usize, usize)> {
+        self.cells
+            .iter()
+            .filter(|(x, y)| board.get_value(*x, *y) == 0)
+            .copied()
+            .collect()
+    }
+}
+
+/// the digit (x, y) must hold because it is the last empty cell of a derived cage, with that
+/// cage. used for hints, the digit is still checked against the board
+pub fn forced_digit(board: &Board, x: usize, y: usize) -> Option<(i32, DerivedCage)> {
+    derived_cages(board).into_iter().find_map(|cage| {
+        if cage.empty_cells(board) != [(x, y)] {
+            return None;
+        }
+        let placed = cage
+            .cells
+            .iter()
+            .map(|(cx, cy)| board.get_value(*cx, *cy))
+            .sum::<i32>();
+        let num = cage.sum - placed;
+        board.is_possible(x, y, num).then_some((num, cage))
+    })
+}
+
+/// how many times derived cages are cut out of each other, more rarely finds anything new
+const ROUNDS: usize = 3;
+
+/// (cells, sum, distinct) of a region or a piece taken out of one
+type Part = (Vec<(usize, usize)>, i32, bool);
+
+/// every derived cage of `board`, only sum cages with a clue take part
+pub fn derived_cages(board: &Board) -> Vec<DerivedCage> {
+    let size = board.get_size();
+    let (box_width, box_height) = board.get_box_size();
+    let house_sum = (size * (size + 1) / 2) as i32;
+    let distinct_cages = !board.get_variants().contains(&Variant::RepeatingCages);
+
+    let cages = board
+        .get_cages()
+        .filter(|(_, cells)| {
+            let (x, y) = cells[0];
+            board.get_operator(x, y) == Operator::Sum && board.get_sum(x, y) > 0
+        })
+        .collect::<Vec<_>>();
+
+    let mut regions: Vec<(Region, Part)> = vec![];
+    for i in 0..size {
+        let row = (0..size).map(|x| (x, i)).collect::<Vec<_>>();
+        let column = (0..size).map(|y| (i, y)).collect::<Vec<_>>();
+        let (box_x, box_y) = (
+            (i % (size / box_width)) * box_width,
+            (i / (size / box_width)) * box_height,
+        );
+        let mut square = (0..size)
+            .map(|o| (box_x + o % box_width, box_y + o / box_width))
+            .collect::<Vec<_>>();
+        square.sort_unstable();
+        regions.push((Region::Row(i), (row, house_sum, true)));
+        regions.push((Region::Column(i), (column, house_sum, true)));
+        regions.push((Region::Box(i), (square, house_sum, true)));
+    }
+    let mut pieces = vec![];
+    for (index, cells) in cages {
+        let (x, y) = cells[0];
+        let mut cells = cells.to_vec();
+        cells.sort_unstable();
+        let part = (cells, board.get_sum(x, y), distinct_cages);
+        regions.push((Region::Cage(index), part.clone()));
+        pieces.push(part);
+    }
+
+    let mut derived: Vec<DerivedCage> = vec![];
+    for _ in 0..ROUNDS {
+        let found = derived.len();
+        for (source, (cells, sum, distinct)) in &regions {
+            let mut left = cells.clone();
+            let mut left_sum = *sum;
+            // cages never overlap, but derived cages may overlap them and each other
+            for (piece, piece_sum, _) in &pieces {
+                if piece.len() < left.len() && piece.iter().all(|c| left.contains(c)) {
+                    left.retain(|c| !piece.contains(c));
+                    left_sum -= piece_sum;
+                }
+            }
+            if left.len() == cells.len() {
+                continue;
+            }
+            let known = pieces.iter().any(|(piece, _, _)| *piece == left)
+                || derived.iter().any(|d| d.cells == left);
+            if !known {
+                derived.push(DerivedCage {
+                    cells: left,
+                    sum: left_sum,
+                    distinct: *distinct,
+                    source: *source,
+                });
+            }
+        }
+        if derived.len() == found {
+            break;
+        }
+        // the new cages can be cut out of the regions and have cages cut out of them
+        for (i, cage) in derived.iter().enumerate().skip(found) {
+            let part = (cage.cells.clone(), cage.sum, cage.distinct);
+            regions.push((Region::Derived(i), part.clone()));
+            pieces.push(part);
+        }
+    }
+    derived
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::board::Board;
+    use crate::data::cage_analysis::{derived_cages, forced_digit, Region};
+    use crate::solvers::backtracking::Backtracking;
+
+    #[test]
+    fn check_derived_cages() {
+        let board = Board::sample_puzzle();
+        let derived = derived_cages(&board);
+        assert!(!derived.is_empty());
+        let solution = Backtracking::new().solve(&board).unwrap();
+        for cage in &derived {
+            let sum = cage
+                .cells
+                .iter()
+                .map(|(x, y)| solution.get_value(*x, *y))
+                .sum::<i32>();
+            assert_eq!(sum, cage.sum, "{:?}", cage);
+        }
+
+        // a 3 in the first two cells of a 4x4 row leaves 7 for the other two
+        let text = "size 4\ncages\n1 1 0 0\n0 0 0 0\n0 0 0 0\n0 0 0 0\nsums\n1 3\n";
+        let mut board = Board::parse(text).unwrap();
+        let row = derived_cages(&board)
+            .into_iter()
+            .find(|d| d.source == Region::Row(0))
+            .unwrap();
+        assert_eq!(row.cells, vec![(2, 0), (3, 0)]);
+        assert_eq!(row.sum, 7);
+        board.unchecked_set_num(2, 0, 3);
+        assert_eq!(forced_digit(&board, 3, 0).map(|(num, _)| num), Some(4));
+    }
+}
@@ -1,49 +1,256 @@
+use crate::data::cage::Operator;
+use std::collections::HashMap;
+
 pub struct CageTable(pub Vec<TableItem>);
 
 impl CageTable {
     pub fn from_file(str: &str) -> Self {
         let file = std::fs::read_to_string(str).unwrap();
-        let lines = file.lines();
+        Self::parse(&file).unwrap()
+    }
 
+    /// parses the table format: a `N cells` header per cage size, then `sum: 123 456` lines
+    /// listing the combinations of that sum
+    pub fn parse(text: &str) -> Result<Self, String> {
         let mut items = vec![];
-
         let mut cage_size = 0;
-        lines.for_each(|line| if line.ends_with("cells") {
-            cage_size += 1;
-        } else if line.contains(':') {
+        for line in text.lines() {
+            if line.ends_with("cells") {
+                cage_size += 1;
+            } else if line.contains(':') {
+                let mut combinations = vec![];
+                // the line has a ':' so it has at least one word
+                let mut split = line.split_whitespace().collect::<Vec<_>>();
+
+                let mut temp_sum = split.remove(0).to_string();
+                temp_sum.pop();
+                let cage_sum = temp_sum
+                    .parse::<i32>()
+                    .map_err(|_| format!("invalid cage sum in '{}'", line))?;
+                // println!("cage size {} {}", cage_size, cage_sum);
+                for possibilities in split {
+                    let possibilities = possibilities
+                        .chars()
+                        .filter_map(|c| c.to_digit(10))
+                        .collect::<Vec<_>>();
+                    combinations.push(possibilities);
+                }
+                // println!("{:?}", combinations);
+                items.push(TableItem {
+                    op: Operator::Sum,
+                    cage_sum,
+                    cage_size,
+                    combinations,
+                })
+            }
+        }
+        Ok(Self(items))
+    }
+
+    /// computes every combination of distinct digits `1..=max_digit`, grouped by cage size and
+    /// sum in the same order as the table file. use this for grids other than 9x9
+    pub fn generate(max_digit: u32) -> Self {
+        Self::generate_operator(max_digit, Operator::Sum)
+    }
+
+    /// like `generate`, for every labelled operator so Calcudoku cages can be looked up too
+    pub fn generate_all(max_digit: u32) -> Self {
+        let items = Operator::ALL
+            .iter()
+            .flat_map(|op| Self::generate_operator(max_digit, *op).0)
+            .collect();
+        Self(items)
+    }
+
+    /// combinations grouped by cage size and the clue they produce under `op`
+    pub fn generate_operator(max_digit: u32, op: Operator) -> Self {
+        let mut items: HashMap<(i32, i32), TableItem> = HashMap::new();
+        for cage_size in 1..=max_digit as i32 {
             let mut combinations = vec![];
-            let mut split = line.split_whitespace().collect::<Vec<_>>();
-
-            let mut temp_sum = split.remove(0).to_string();
-            temp_sum.pop();
-            let cage_sum = temp_sum.parse::<i32>().unwrap();
-            // println!("cage size {} {}", cage_size, cage_sum);
-            for possibilities in split {
-                let possibilities = possibilities
-                    .chars()
-                    .filter(|x| x.is_digit(10))
-                    .map(|c| c.to_digit(10).unwrap())
-                    .collect::<Vec<_>>();
-                combinations.push(possibilities);
+            push_combinations(
+                &mut combinations,
+                &mut vec![],
+                1,
+                max_digit,
+                cage_size as usize,
+            );
+            for combination in combinations {
+                let digits = combination.iter().map(|d| *d as i32).collect::<Vec<_>>();
+                let cage_sum = match op.evaluate(&digits) {
+                    Some(clue) => clue,
+                    None => continue,
+                };
+                items
+                    .entry((cage_size, cage_sum))
+                    .or_insert_with(|| TableItem {
+                        op,
+                        cage_size,
+                        cage_sum,
+                        combinations: vec![],
+                    })
+                    .combinations
+                    .push(combination);
             }
-            // println!("{:?}", combinations);
-            items.push(TableItem {
-                cage_sum,
-                cage_size,
-                combinations,
-            })
-        });
+        }
+        let mut items = items.into_values().collect::<Vec<_>>();
+        items.sort_by_key(|ti| (ti.cage_size, ti.cage_sum));
         Self(items)
     }
 
+    /// the combinations of `cage_size` distinct digits `1..=max_digit` that make `clue` under
+    /// `op`, without building the rest of the table. digits that can't reach the clue any more
+    /// aren't extended, so this stays quick on the largest boards
+    pub fn lookup(max_digit: u32, op: Operator, cage_size: i32, clue: i32) -> Option<TableItem> {
+        let mut combinations = vec![];
+        let len = usize::try_from(cage_size).ok()?;
+        push_reaching(&mut combinations, &mut vec![], max_digit, len, op, clue);
+        (!combinations.is_empty()).then_some(TableItem {
+            op,
+            cage_size,
+            cage_sum: clue,
+            combinations,
+        })
+    }
+
     pub fn find(&mut self, size: i32, sum: i32) -> Option<TableItem> {
-        self.0.iter().find(|ti| ti.cage_sum == sum && ti.cage_size == size).cloned()
+        self.find_operator(Operator::Sum, size, sum)
+    }
+
+    pub fn find_operator(&mut self, op: Operator, size: i32, clue: i32) -> Option<TableItem> {
+        self.0
+            .iter()
+            .find(|ti| ti.op == op && ti.cage_sum == clue && ti.cage_size == size)
+            .cloned()
+    }
+}
+
+/// pushes every increasing combination of `len` digits from `next..=max` onto `out`
+fn push_combinations(
+    out: &mut Vec<Vec<u32>>,
+    current: &mut Vec<u32>,
+    next: u32,
+    max: u32,
+    len: usize,
+) {
+    if current.len() == len {
+        out.push(current.clone());
+        return;
+    }
+    for digit in next..=max {
+        current.push(digit);
+        push_combinations(out, current, digit + 1, max, len);
+        current.pop();
+    }
+}
+
+/// like `push_combinations`, only the combinations that make `clue` under `op`
+fn push_reaching(
+    out: &mut Vec<Vec<u32>>,
+    current: &mut Vec<u32>,
+    max: u32,
+    len: usize,
+    op: Operator,
+    clue: i32,
+) {
+    if current.len() == len {
+        let digits = current.iter().map(|d| *d as i32).collect::<Vec<_>>();
+        if op.evaluate(&digits) == Some(clue) {
+            out.push(current.clone());
+        }
+        return;
+    }
+    let next = current.last().map_or(1, |d| d + 1);
+    for digit in next..=max {
+        current.push(digit);
+        if can_reach(current, len - current.len(), max, op, clue) {
+            push_reaching(out, current, max, len, op, clue);
+        }
+        current.pop();
+    }
+}
+
+/// whether increasing `digits` followed by `left` larger digits up to `max` can still make
+/// `clue`. the digits are ascending, so until the last one they are all below the largest
+fn can_reach(digits: &[u32], left: usize, max: u32, op: Operator, clue: i32) -> bool {
+    let last = *digits.last().unwrap() as i64;
+    let (left, max, clue) = (left as i64, max as i64, clue as i64);
+    if last + left > max {
+        return false;
+    }
+    let sum = digits.iter().map(|d| *d as i64).sum::<i64>();
+    let product = digits.iter().try_fold(1i64, |p, d| {
+        p.checked_mul(*d as i64).filter(|p| *p <= i32::MAX as i64)
+    });
+    match op {
+        Operator::Sum => {
+            // the next `left` digits at least, the top `left` digits at most
+            let smallest = (last + 1..=last + left).sum::<i64>();
+            let largest = (max - left + 1..=max).sum::<i64>();
+            sum + smallest <= clue && clue <= sum + largest
+        }
+        Operator::Product => product.is_some_and(|p| clue % p == 0),
+        // every digit so far is taken away from (or divides) the largest one still to come
+        Operator::Difference => left == 0 || sum + clue <= max,
+        Operator::Quotient => left == 0 || product.is_some_and(|p| p * clue <= max),
+        Operator::Unlabelled => false,
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct TableItem {
+    pub op: Operator,
     pub cage_size: i32,
+    /// the clue, a sum unless `op` says otherwise
     pub cage_sum: i32,
     pub combinations: Vec<Vec<u32>>,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use crate::data::cage::Operator;
+    use crate::data::cage_table::CageTable;
+
+    #[test]
+    fn check_generate_operator_overflow() {
+        // products of 13 or more digits don't fit an i32, they are left out instead of wrapping
+        let table = CageTable::generate_operator(16, Operator::Product);
+        let product = (1..=12).product::<i32>();
+        assert!(table
+            .0
+            .iter()
+            .any(|ti| ti.cage_size == 12 && ti.cage_sum == product));
+        assert!(table
+            .0
+            .iter()
+            .all(|ti| ti.cage_size <= 12 && ti.cage_sum > 0));
+    }
+
+    #[test]
+    fn check_lookup() {
+        // the same combinations as the whole table, for every clue it holds
+        for op in Operator::ALL {
+            let table = CageTable::generate_operator(7, op);
+            for item in &table.0 {
+                let found = CageTable::lookup(7, op, item.cage_size, item.cage_sum).unwrap();
+                assert_eq!(found.combinations, item.combinations);
+            }
+            assert!(CageTable::lookup(7, op, 2, 100).is_none());
+        }
+        // a 25x25 board only builds the cage it asks for
+        let item = CageTable::lookup(25, Operator::Sum, 12, 78).unwrap();
+        assert_eq!(item.combinations, vec![(1..=12).collect::<Vec<_>>()]);
+        assert!(CageTable::lookup(25, Operator::Product, 12, 479001600).is_some());
+    }
+
+    #[test]
+    fn check_generate_matches_file() {
+        let file = CageTable::from_file("./assets/cage_table.txt");
+        let generated = CageTable::generate(9);
+        assert_eq!(file.0.len(), generated.0.len());
+        for (a, b) in file.0.iter().zip(generated.0.iter()) {
+            assert_eq!(
+                (a.cage_size, a.cage_sum, &a.combinations),
+                (b.cage_size, b.cage_sum, &b.combinations)
+            );
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use crate::data::board::Board;
+use crate::data::format;
+use crate::solvers::deduction::Grade;
+use std::path::Path;
+
+/// the graded test puzzles
+pub const CORPUS_DIR: &str = "./assets/corpus";
+
+/// A puzzle of the corpus with what solving it should give.
+///
+/// Besides the puzzle a file has a `solutions none|unique|multiple` line and, for unique
+/// puzzles, the `grade` deducing it should give. The `solution` section holds the solution, or
+/// one of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorpusPuzzle {
+    /// the file name without `.txt`, e.g. `hard-2`
+    pub name: String,
+    pub board: Board,
+    pub solutions: Solutions,
+    pub grade: Option<Grade>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Solutions {
+    None,
+    Unique,
+    Multiple,
+}
+
+impl Solutions {
+    pub const ALL: [Solutions; 3] = [Solutions::None, Solutions::Unique, Solutions::Multiple];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Solutions::None => "none",
+            Solutions::Unique => "unique",
+            Solutions::Multiple => "multiple",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|s| s.name() == name).copied()
+    }
+}
+
+impl CorpusPuzzle {
+    pub fn parse(name: &str, text: &str) -> Result<Self, String> {
+        let board = Board::parse(text)?;
+        let sections = format::sections(text);
+        let arg = |section: &str| {
+            format::find(&sections, section)
+                .and_then(|s| s.args.first())
+                .ok_or(format!("{} has no {} line", name, section))
+        };
+        let solutions = arg("solutions")?;
+        let solutions = Solutions::from_name(solutions)
+            .ok_or(format!("unknown solution count '{}'", solutions))?;
+        let grade = match solutions {
+            Solutions::Unique => {
+                let grade = arg("grade")?;
+                Some(Grade::from_name(grade).ok_or(format!("unknown grade '{}'", grade))?)
+            }
+            _ => None,
+        };
+        Ok(Self {
+            name: name.to_string(),
+            board,
+            solutions,
+            grade,
+        })
+    }
+
+    /// "9x9 hard-2", to label results
+    pub fn label(&self) -> String {
+        let size = self.board.get_size();
+        format!("{}x{} {}", size, size, self.name)
+    }
+}
+
+/// every puzzle in `dir`, sorted by name
+pub fn load(dir: &Path) -> Result<Vec<CorpusPuzzle>, String> {
+    let mut paths = std::fs::read_dir(dir)
+        .map_err(|e| format!("could not read {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "txt"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| {
+            let name = path.file_stem().unwrap_or_default().to_string_lossy();
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+            CorpusPuzzle::parse(&name, &text).map_err(|e| format!("{}: {}", name, e))
+        })
+        .collect()
+}
+
+/// the puzzles in `CORPUS_DIR`
+pub fn load_corpus() -> Vec<CorpusPuzzle> {
+    load(Path::new(CORPUS_DIR)).unwrap()
+}
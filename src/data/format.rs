@@ -0,0 +1,84 @@
+/// A named block in the plain-text formats used for puzzles and saved games.
+///
+/// A section starts with a header line whose first word is alphabetic (e.g. `grid` or
+/// `elapsed 12.5`), every following line up to the next header belongs to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    pub name: String,
+    pub args: Vec<String>,
+    pub lines: Vec<String>,
+}
+
+pub fn sections(text: &str) -> Vec<Section> {
+    let mut sections: Vec<Section> = vec![];
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let first = words.next().unwrap();
+        if first.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            sections.push(Section {
+                name: first.to_string(),
+                args: words.map(|w| w.to_string()).collect(),
+                lines: vec![],
+            });
+        } else if let Some(section) = sections.last_mut() {
+            section.lines.push(line.to_string());
+        }
+    }
+    sections
+}
+
+pub fn find<'a>(sections: &'a [Section], name: &str) -> Option<&'a Section> {
+    sections.iter().find(|s| s.name == name)
+}
+
+/// parses every line of a section as whitespace separated numbers
+pub fn parse_numbers<T: std::str::FromStr>(section: &Section) -> Result<Vec<Vec<T>>, String> {
+    section
+        .lines
+        .iter()
+        .map(|line| {
+            line.split_whitespace()
+                .map(|word| {
+                    word.parse::<T>()
+                        .map_err(|_| format!("invalid number '{}' in {}", word, section.name))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// parses a section as a `size` x `size` grid of numbers
+pub fn parse_grid(section: &Section, size: usize) -> Result<Vec<Vec<i32>>, String> {
+    let rows = parse_numbers::<i32>(section)?;
+    if rows.len() != size || rows.iter().any(|row| row.len() != size) {
+        return Err(format!("{} should be {}x{}", section.name, size, size));
+    }
+    Ok(rows)
+}
+
+/// parses a section as a `size` x `size` grid of digits, 0 for an empty cell
+pub fn parse_digits(section: &Section, size: usize) -> Result<Vec<Vec<i32>>, String> {
+    let rows = parse_grid(section, size)?;
+    match rows
+        .iter()
+        .flatten()
+        .find(|n| !(0..=size as i32).contains(n))
+    {
+        Some(n) => Err(format!("{} in {} is not a digit", n, section.name)),
+        None => Ok(rows),
+    }
+}
+
+pub fn write_grid(out: &mut String, name: &str, grid: &[Vec<i32>]) {
+    out.push_str(name);
+    out.push('\n');
+    for row in grid {
+        let row = row.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        out.push_str(&row.join(" "));
+        out.push('\n');
+    }
+}
@@ -0,0 +1,321 @@
+use crate::data::board::{Board, Move};
+use crate::data::format;
+use std::path::{Path, PathBuf};
+
+/// directory the Bevy app autosaves in-progress games to
+pub const SAVE_DIR: &str = "./saves";
+
+/// a single player action, kept so it can be undone
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    Value {
+        x: usize,
+        y: usize,
+        old: i32,
+        new: i32,
+    },
+    Note {
+        x: usize,
+        y: usize,
+        num: i32,
+    },
+}
+
+/// an in-progress game: the puzzle as it was handed out plus everything the player did since
+#[derive(Debug, Clone, PartialEq)]
+pub struct Game {
+    puzzle: Board,
+    board: Board,
+    notes: Vec<Vec<Vec<i32>>>,
+    history: Vec<Edit>,
+    pub elapsed: f64,
+}
+
+impl Game {
+    pub fn new(puzzle: Board) -> Self {
+        let size = puzzle.get_size();
+        Self {
+            board: puzzle.clone(),
+            puzzle,
+            notes: vec![vec![vec![]; size]; size],
+            history: vec![],
+            elapsed: 0.,
+        }
+    }
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+    pub fn is_given(&self, x: usize, y: usize) -> bool {
+        self.board.is_given(x, y)
+    }
+    /// enters `num` in an empty or player-filled cell, `0` clears it.
+    /// the cell keeps its digit unless the move is applied
+    pub fn set_num(&mut self, x: usize, y: usize, num: i32) -> Move {
+        if self.is_given(x, y) {
+            return Move::Given;
+        }
+        let old = self.board.get_value(x, y);
+        if old == num {
+            return if num == 0 {
+                Move::Empty
+            } else {
+                Move::Occupied
+            };
+        }
+
+        self.board.take(x, y);
+        if num != 0 {
+            let result = self.board.set_num(x, y, num);
+            if result != Move::Applied {
+                self.board.enter_num(x, y, old);
+                return result;
+            }
+        }
+        self.history.push(Edit::Value {
+            x,
+            y,
+            old,
+            new: num,
+        });
+        Move::Applied
+    }
+    pub fn get_notes(&self, x: usize, y: usize) -> &[i32] {
+        &self.notes[y][x]
+    }
+    pub fn toggle_note(&mut self, x: usize, y: usize, num: i32) {
+        if self.is_given(x, y) {
+            return;
+        }
+        self.flip_note(x, y, num);
+        self.history.push(Edit::Note { x, y, num });
+    }
+    fn flip_note(&mut self, x: usize, y: usize, num: i32) {
+        let notes = &mut self.notes[y][x];
+        if let Some(pos) = notes.iter().position(|n| *n == num) {
+            notes.remove(pos);
+        } else {
+            notes.push(num);
+            notes.sort_unstable();
+        }
+    }
+    /// reverts the last edit, returns false if there was nothing to undo
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            Some(Edit::Value { x, y, old, .. }) => self.board.enter_num(x, y, old),
+            Some(Edit::Note { x, y, num }) => self.flip_note(x, y, num),
+            None => return false,
+        }
+        true
+    }
+    /// back to the puzzle as handed out, without entries, notes or history. the clock keeps
+    /// running
+    pub fn reset(&mut self) {
+        self.board.reset_to_givens();
+        let size = self.board.get_size();
+        self.notes = vec![vec![vec![]; size]; size];
+        self.history.clear();
+    }
+    pub fn get_history(&self) -> &[Edit] {
+        &self.history
+    }
+    pub fn is_finished(&self) -> bool {
+        self.board.is_finished()
+    }
+    /// writes the game in the puzzle format, extended with `elapsed`, `entries`, `notes` and
+    /// `history` sections
+    pub fn serialize(&self) -> String {
+        let mut out = format!("elapsed {}\n", self.elapsed);
+        out.push_str(&self.puzzle.serialize());
+        format::write_grid(&mut out, "entries", &self.board.clone().get_grid());
+
+        out.push_str("notes\n");
+        for (y, row) in self.notes.iter().enumerate() {
+            for (x, notes) in row.iter().enumerate().filter(|(_, n)| !n.is_empty()) {
+                let notes = notes.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+                out.push_str(&format!("{} {} {}\n", x, y, notes.join(" ")));
+            }
+        }
+
+        out.push_str("history\n");
+        for edit in &self.history {
+            match edit {
+                Edit::Value { x, y, old, new } => {
+                    out.push_str(&format!("1 {} {} {} {}\n", x, y, old, new))
+                }
+                Edit::Note { x, y, num } => out.push_str(&format!("2 {} {} {}\n", x, y, num)),
+            }
+        }
+        out
+    }
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let sections = format::sections(text);
+        let mut game = Game::new(Board::parse(text)?);
+        let size = game.board.get_size();
+
+        if let Some(section) = format::find(&sections, "elapsed") {
+            game.elapsed = section
+                .args
+                .first()
+                .and_then(|a| a.parse().ok())
+                .ok_or("elapsed should be a number of seconds")?;
+        }
+        if let Some(section) = format::find(&sections, "entries") {
+            for (y, row) in format::parse_digits(section, size)?.iter().enumerate() {
+                for (x, num) in row.iter().enumerate() {
+                    if !game.is_given(x, y) {
+                        game.board.enter_num(x, y, *num);
+                    } else if *num != game.board.get_value(x, y) {
+                        return Err(format!(
+                            "entry {} at ({}, {}) differs from the given",
+                            num, x, y
+                        ));
+                    }
+                }
+            }
+        }
+        if let Some(section) = format::find(&sections, "notes") {
+            for line in format::parse_numbers::<usize>(section)? {
+                match line[..] {
+                    [x, y, ref notes @ ..] if x < size && y < size => {
+                        if let Some(n) = notes.iter().find(|n| !(1..=size).contains(*n)) {
+                            return Err(format!("{} in notes is not a digit", n));
+                        }
+                        let mut notes = notes.iter().map(|n| *n as i32).collect::<Vec<_>>();
+                        notes.sort_unstable();
+                        notes.dedup();
+                        game.notes[y][x] = notes;
+                    }
+                    _ => return Err("notes should be 'x y digits..'".to_string()),
+                }
+            }
+        }
+        if let Some(section) = format::find(&sections, "history") {
+            let digit = |n: i32, from: i32| (from..=size as i32).contains(&n);
+            for line in format::parse_numbers::<i32>(section)? {
+                if line
+                    .iter()
+                    .skip(1)
+                    .take(2)
+                    .any(|c| *c < 0 || *c as usize >= size)
+                {
+                    return Err("history entry outside the grid".to_string());
+                }
+                let edit = match line[..] {
+                    [1, x, y, old, new] if digit(old, 0) && digit(new, 0) => Edit::Value {
+                        x: x as usize,
+                        y: y as usize,
+                        old,
+                        new,
+                    },
+                    [2, x, y, num] if digit(num, 1) => Edit::Note {
+                        x: x as usize,
+                        y: y as usize,
+                        num,
+                    },
+                    [1 | 2, ..] => return Err(format!("bad history entry {:?}", line)),
+                    _ => return Err("unknown history entry".to_string()),
+                };
+                // undoing it would change a given
+                let (x, y) = (line[1] as usize, line[2] as usize);
+                if game.is_given(x, y) {
+                    return Err(format!("history entry on the given cell ({}, {})", x, y));
+                }
+                game.history.push(edit);
+            }
+        }
+        Ok(game)
+    }
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, self.serialize())
+    }
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::parse(&text)
+    }
+}
+
+/// saved games in `SAVE_DIR`, most recently played first
+pub fn list_saves() -> Vec<PathBuf> {
+    let mut saves = match std::fs::read_dir(SAVE_DIR) {
+        Ok(dir) => dir
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().extension().is_some_and(|e| e == "txt"))
+            .map(|entry| {
+                let modified = entry.metadata().and_then(|m| m.modified()).ok();
+                (modified, entry.path())
+            })
+            .collect::<Vec<_>>(),
+        Err(_) => vec![],
+    };
+    saves.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    saves.into_iter().map(|(_, path)| path).collect()
+}
+
+/// a fresh file name in `SAVE_DIR` for a new game
+pub fn new_save_path() -> PathBuf {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    Path::new(SAVE_DIR).join(format!("game-{}.txt", secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::board::{Board, Move};
+    use crate::data::game::Game;
+
+    #[test]
+    fn check_undo_and_save() {
+        let mut game = Game::new(Board::sample_puzzle());
+        assert_eq!(game.set_num(0, 0, 2), Move::Applied);
+        assert_eq!(game.set_num(1, 0, 9), Move::Conflict);
+        game.toggle_note(1, 0, 1);
+        game.elapsed = 12.5;
+        assert_eq!(Game::parse(&game.serialize()), Ok(game.clone()));
+
+        assert!(game.undo());
+        assert!(game.get_notes(1, 0).is_empty());
+        assert!(game.undo());
+        assert_eq!(game.get_board().get_value(0, 0), 0);
+        assert!(!game.undo());
+
+        // notes are digits, kept sorted without repeats like toggling them keeps them
+        let puzzle = Board::sample_puzzle().serialize();
+        let game = Game::parse(&format!("{}notes\n1 0 5 2 5\n", puzzle)).unwrap();
+        assert_eq!(game.get_notes(1, 0), &[2, 5]);
+        for bad in ["1 0 0", "1 0 10"] {
+            assert!(Game::parse(&format!("{}notes\n{}\n", puzzle, bad)).is_err());
+        }
+
+        // undo writes history digits into the grid, so they have to be digits of empty cells
+        let given = {
+            let mut board = Board::sample_puzzle();
+            board.unchecked_set_num(0, 0, 2);
+            board.mark_givens();
+            board.serialize()
+        };
+        assert!(Game::parse(&format!("{}history\n1 1 0 0 5\n2 1 0 9\n", given)).is_ok());
+        for bad in [
+            "1 1 0 99 5",
+            "1 1 0 0 -1",
+            "2 1 0 0",
+            "2 1 0 10",
+            "1 0 0 0 5",
+        ] {
+            assert!(Game::parse(&format!("{}history\n{}\n", given, bad)).is_err());
+        }
+
+        // entries repeat the givens, they may not change them
+        let mut game = Game::parse(&given).unwrap();
+        assert_eq!(game.set_num(1, 0, 1), Move::Applied);
+        assert_eq!(Game::parse(&game.serialize()), Ok(game.clone()));
+        for changed in ["entries\n0 1", "entries\n3 1"] {
+            let text = game.serialize().replacen("entries\n2 1", changed, 1);
+            assert!(Game::parse(&text).is_err());
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use crate::data::board::Board;
+use crate::solvers::backtracking::Backtracking;
+use crate::solvers::deduction::{deduce, Grade};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+/// cage sizes to pick from, pairs and triples are the most common like in printed puzzles
+const CAGE_SIZES: [usize; 9] = [2, 2, 2, 3, 3, 3, 4, 4, 5];
+
+/// the largest cage merging two cages may make
+const MAX_MERGED: usize = 5;
+
+/// A killer puzzle made from a seed, the same seed always makes the same puzzle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Generated {
+    pub seed: u64,
+    /// the puzzle with its solution stored
+    pub board: Board,
+    pub grade: Grade,
+}
+
+/// a killer puzzle of `size` with a single solution. while two solutions differ, the cage of a
+/// cell where they do is merged with a neighbouring cage, only when no merge keeps the digits of
+/// a cage different is the cell given
+pub fn generate(size: usize, seed: u64) -> Generated {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let solution = solved_grid(size, &mut rng);
+    let mut board = Board::new(size);
+    place_cages(&mut board, &solution, &mut rng);
+    board.set_solution(&solution);
+
+    let solver = Backtracking::new();
+    loop {
+        let solutions = solver.solutions(&board, 2);
+        if solutions.len() < 2 {
+            break;
+        }
+        let mut differing = (0..size * size)
+            .map(|i| (i % size, i / size))
+            .filter(|(x, y)| solutions[0].get_value(*x, *y) != solutions[1].get_value(*x, *y))
+            .collect::<Vec<_>>();
+        differing.shuffle(&mut rng);
+        match differing
+            .iter()
+            .find_map(|(x, y)| mergeable(&board, &solution, *x, *y))
+        {
+            Some(cells) => {
+                board.merge_cage(&cells);
+            }
+            None => {
+                let (x, y) = differing[0];
+                board.unchecked_set_num(x, y, solution.get_value(x, y));
+            }
+        }
+    }
+    board.mark_givens();
+
+    let grade = deduce(&board).grade();
+    Generated { seed, board, grade }
+}
+
+/// (x, y) and a neighbour in another cage, if their cages together hold different digits and
+/// no more than `MAX_MERGED` cells
+fn mergeable(board: &Board, solution: &Board, x: usize, y: usize) -> Option<Vec<(usize, usize)>> {
+    let cage = board.get_cage_cells(x, y);
+    neighbours(x, y, board.get_size())
+        .into_iter()
+        .filter(|(nx, ny)| board.get_cage_index(*nx, *ny) != board.get_cage_index(x, y))
+        .find(|(nx, ny)| {
+            let mut cells = cage.clone();
+            cells.extend(board.get_cage_cells(*nx, *ny));
+            let mut digits = cells
+                .iter()
+                .map(|(cx, cy)| solution.get_value(*cx, *cy))
+                .collect::<Vec<_>>();
+            digits.sort_unstable();
+            digits.dedup();
+            cells.len() <= MAX_MERGED && digits.len() == cells.len()
+        })
+        .map(|neighbour| vec![(x, y), neighbour])
+}
+
+/// a random full grid: a shifted pattern with its digits, rows, columns, bands and stacks
+/// shuffled, which keeps every row, column and box complete
+fn solved_grid(size: usize, rng: &mut StdRng) -> Board {
+    let mut board = Board::new(size);
+    let (box_width, box_height) = board.get_box_size();
+    let mut digits = (1..=size as i32).collect::<Vec<_>>();
+    digits.shuffle(rng);
+    let rows = shuffled_lines(box_height, size / box_height, rng);
+    let columns = shuffled_lines(box_width, size / box_width, rng);
+    for (y, row) in rows.iter().enumerate() {
+        for (x, column) in columns.iter().enumerate() {
+            let i = (box_width * (row % box_height) + row / box_height + column) % size;
+            board.unchecked_set_num(x, y, digits[i]);
+        }
+    }
+    board
+}
+
+/// the lines of `groups` groups of `width`, shuffled within their group and by group
+fn shuffled_lines(width: usize, groups: usize, rng: &mut StdRng) -> Vec<usize> {
+    let mut order = (0..groups).collect::<Vec<_>>();
+    order.shuffle(rng);
+    let mut lines = vec![];
+    for group in order {
+        let mut group = (group * width..(group + 1) * width).collect::<Vec<_>>();
+        group.shuffle(rng);
+        lines.extend(group);
+    }
+    lines
+}
+
+/// grows cages from the cells in random order until every cell is in one, a cage only takes
+/// cells whose digit it doesn't hold yet
+fn place_cages(board: &mut Board, solution: &Board, rng: &mut StdRng) {
+    let size = board.get_size();
+    let mut cells = (0..size * size)
+        .map(|i| (i % size, i / size))
+        .collect::<Vec<_>>();
+    cells.shuffle(rng);
+    for start in cells {
+        if board.get_cage_index(start.0, start.1) != 0 {
+            continue;
+        }
+        let target = *CAGE_SIZES.choose(rng).unwrap();
+        let mut cage = vec![start];
+        while cage.len() < target {
+            let digits = cage
+                .iter()
+                .map(|(x, y)| solution.get_value(*x, *y))
+                .collect::<Vec<_>>();
+            let free = cage
+                .iter()
+                .flat_map(|(x, y)| neighbours(*x, *y, size))
+                .filter(|(x, y)| {
+                    board.get_cage_index(*x, *y) == 0
+                        && !cage.contains(&(*x, *y))
+                        && !digits.contains(&solution.get_value(*x, *y))
+                })
+                .collect::<Vec<_>>();
+            if free.is_empty() {
+                break;
+            }
+            cage.push(free[rng.gen_range(0..free.len())]);
+        }
+        let index = board.merge_cage(&cage);
+        let sum = cage.iter().map(|(x, y)| solution.get_value(*x, *y)).sum();
+        board.set_cage_sum(index, sum);
+    }
+}
+
+fn neighbours(x: usize, y: usize, size: usize) -> Vec<(usize, usize)> {
+    let mut cells = vec![];
+    if x > 0 {
+        cells.push((x - 1, y));
+    }
+    if y > 0 {
+        cells.push((x, y - 1));
+    }
+    if x + 1 < size {
+        cells.push((x + 1, y));
+    }
+    if y + 1 < size {
+        cells.push((x, y + 1));
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::generator::generate;
+    use crate::solvers::backtracking::Backtracking;
+
+    #[test]
+    fn check_generate() {
+        let puzzle = generate(6, 7);
+        let board = &puzzle.board;
+        assert!(board.validate_cages(false).is_ok());
+        assert_eq!(Backtracking::new().count_solutions(board, 2), 1);
+        let solution = Backtracking::new().solve(board).unwrap();
+        for y in 0..6 {
+            for x in 0..6 {
+                assert_eq!(board.get_solution()[y][x], solution.get_value(x, y));
+            }
+        }
+        // the same seed makes the same puzzle
+        assert_eq!(generate(6, 7), puzzle);
+    }
+}
@@ -1,4 +1,10 @@
 pub mod board;
 pub mod cage;
-pub mod node;
+pub mod cage_analysis;
 pub mod cage_table;
+pub mod corpus;
+pub mod format;
+pub mod game;
+pub mod generator;
+pub mod node;
+pub mod variant;
@@ -1,28 +1,74 @@
 use crate::data::board::Board;
+use serde::Serialize;
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct Node<T> {
     pub children: Vec<Node<T>>,
     data: Option<T>,
 }
 
-impl Node<Board> {
-    pub fn new(data: Board) -> Self {
+impl<T> Node<T> {
+    pub fn new(data: T) -> Self {
         Self {
             data: Some(data),
             children: vec![],
         }
     }
-    pub fn add_child(&mut self, node: &Node<Board>) {
-        self.children.push(node.clone());
+    /// moves `node` into the tree, returning it for further building
+    pub fn add_child(&mut self, node: Node<T>) -> &mut Node<T> {
+        self.children.push(node);
+        self.children.last_mut().unwrap()
     }
-    pub fn from_board(board: Board) -> Self {
-        Self {
-            children: vec![],
-            data: Some(board),
+    pub fn get_data(&self) -> &Option<T> {
+        &self.data
+    }
+    #[deprecated(note = "renamed to `get_data`")]
+    pub fn get_date(&self) -> &Option<T> {
+        self.get_data()
+    }
+    pub fn get_data_mut(&mut self) -> &mut Option<T> {
+        &mut self.data
+    }
+    /// follows child indices from this node
+    pub fn get(&self, path: &[usize]) -> Option<&Node<T>> {
+        match path.split_first() {
+            Some((first, rest)) => self.children.get(*first)?.get(rest),
+            None => Some(self),
         }
     }
-    pub fn get_date(&self) -> &Option<Board> {
-        &self.data
+    /// every node below and including this one, depth-first. nodes still to visit are kept
+    /// on a stack, so deep trees don't recurse
+    pub fn iter(&self) -> impl Iterator<Item = &Node<T>> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+}
+
+impl Node<Board> {
+    pub fn from_board(board: Board) -> Self {
+        Self::new(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::node::Node;
+
+    #[test]
+    fn check_iter() {
+        let mut root = Node::new(0);
+        let first = root.add_child(Node::new(1));
+        first.add_child(Node::new(2));
+        first.add_child(Node::new(3));
+        root.add_child(Node::new(4));
+        let order = root
+            .iter()
+            .map(|node| node.get_data().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec![0, 1, 2, 3, 4]);
     }
 }
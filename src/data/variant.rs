@@ -0,0 +1,200 @@
+use crate::data::board::Board;
+
+/// An extra rule on top of the rows, columns, boxes and cage sums every puzzle has.
+///
+/// Most rules only forbid some cells from sharing a digit, those just implement `peers`.
+/// Rules that need something else can override `is_possible` and `is_finished` instead.
+pub trait Constraint {
+    /// cells that may not hold the same digit as (x, y)
+    fn peers(&self, _board: &Board, _x: usize, _y: usize) -> Vec<(usize, usize)> {
+        vec![]
+    }
+    /// whether `num` may be placed in the empty cell (x, y)
+    fn is_possible(&self, board: &Board, x: usize, y: usize, num: i32) -> bool {
+        self.peers(board, x, y)
+            .iter()
+            .all(|(px, py)| board.get_value(*px, *py) != num)
+    }
+    /// whether a completely filled board satisfies the rule
+    fn is_finished(&self, board: &Board) -> bool {
+        let size = board.get_size();
+        (0..size * size).all(|i| {
+            let (x, y) = (i % size, i / size);
+            let num = board.get_value(x, y);
+            self.peers(board, x, y)
+                .iter()
+                .all(|(px, py)| board.get_value(*px, *py) != num)
+        })
+    }
+}
+
+/// The rule variants a puzzle can select, stored in the puzzle format's `variants` section.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Variant {
+    /// killer-X: both main diagonals hold every digit once
+    Diagonal,
+    /// four extra box-sized regions (windoku / hyper sudoku)
+    Windoku,
+    /// cells a chess knight's move apart differ
+    AntiKnight,
+    /// cells a chess king's move apart (including diagonally) differ
+    AntiKing,
+    /// digits may repeat inside a cage, which drops the `UniqueCages` rule
+    RepeatingCages,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 5] = [
+        Variant::Diagonal,
+        Variant::Windoku,
+        Variant::AntiKnight,
+        Variant::AntiKing,
+        Variant::RepeatingCages,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Variant::Diagonal => "diagonal",
+            Variant::Windoku => "windoku",
+            Variant::AntiKnight => "anti-knight",
+            Variant::AntiKing => "anti-king",
+            Variant::RepeatingCages => "repeating-cages",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|v| v.name() == name).copied()
+    }
+    /// the rule this variant adds, `None` for variants that only relax the default rules
+    pub fn constraint(&self) -> Option<&'static dyn Constraint> {
+        match self {
+            Variant::Diagonal => Some(&Diagonal),
+            Variant::Windoku => Some(&Windoku),
+            Variant::AntiKnight => Some(&AntiKnight),
+            Variant::AntiKing => Some(&AntiKing),
+            Variant::RepeatingCages => None,
+        }
+    }
+}
+
+/// the regular killer rule: a cage never holds the same digit twice
+pub struct UniqueCages;
+
+impl Constraint for UniqueCages {
+    fn peers(&self, board: &Board, x: usize, y: usize) -> Vec<(usize, usize)> {
+        if board.get_cage_index(x, y) == 0 {
+            return vec![];
+        }
+        board
+            .get_cage_cells(x, y)
+            .into_iter()
+            .filter(|cell| *cell != (x, y))
+            .collect()
+    }
+}
+
+pub struct Diagonal;
+
+impl Constraint for Diagonal {
+    fn peers(&self, board: &Board, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let size = board.get_size();
+        let mut peers = vec![];
+        if x == y {
+            peers.extend((0..size).map(|i| (i, i)));
+        }
+        if x + y == size - 1 {
+            peers.extend((0..size).map(|i| (i, size - 1 - i)));
+        }
+        peers.retain(|cell| *cell != (x, y));
+        peers
+    }
+}
+
+pub struct Windoku;
+
+impl Windoku {
+    /// top-left corners of the extra regions, each one cell in from the previous box
+    fn windows(board: &Board) -> Vec<(usize, usize)> {
+        let size = board.get_size();
+        let (box_width, box_height) = board.get_box_size();
+        let xs = (1..)
+            .step_by(box_width + 1)
+            .take_while(|x| x + box_width < size);
+        let ys = (1..)
+            .step_by(box_height + 1)
+            .take_while(|y| y + box_height < size)
+            .collect::<Vec<_>>();
+        xs.flat_map(|x| ys.iter().map(move |y| (x, *y))).collect()
+    }
+}
+
+impl Constraint for Windoku {
+    fn peers(&self, board: &Board, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let (box_width, box_height) = board.get_box_size();
+        Windoku::windows(board)
+            .into_iter()
+            .filter(|(wx, wy)| {
+                (*wx..wx + box_width).contains(&x) && (*wy..wy + box_height).contains(&y)
+            })
+            .flat_map(|(wx, wy)| {
+                (wy..wy + box_height)
+                    .flat_map(move |py| (wx..wx + box_width).map(move |px| (px, py)))
+            })
+            .filter(|cell| *cell != (x, y))
+            .collect()
+    }
+}
+
+/// cells at the given offsets from (x, y) that lie on the board
+fn offsets(board: &Board, x: usize, y: usize, offsets: &[(i32, i32)]) -> Vec<(usize, usize)> {
+    let size = board.get_size() as i32;
+    offsets
+        .iter()
+        .map(|(dx, dy)| (x as i32 + dx, y as i32 + dy))
+        .filter(|(px, py)| (0..size).contains(px) && (0..size).contains(py))
+        .map(|(px, py)| (px as usize, py as usize))
+        .collect()
+}
+
+pub struct AntiKnight;
+
+impl Constraint for AntiKnight {
+    fn peers(&self, board: &Board, x: usize, y: usize) -> Vec<(usize, usize)> {
+        offsets(
+            board,
+            x,
+            y,
+            &[
+                (1, 2),
+                (2, 1),
+                (2, -1),
+                (1, -2),
+                (-1, -2),
+                (-2, -1),
+                (-2, 1),
+                (-1, 2),
+            ],
+        )
+    }
+}
+
+pub struct AntiKing;
+
+impl Constraint for AntiKing {
+    fn peers(&self, board: &Board, x: usize, y: usize) -> Vec<(usize, usize)> {
+        offsets(
+            board,
+            x,
+            y,
+            &[
+                (1, 1),
+                (1, 0),
+                (1, -1),
+                (0, -1),
+                (-1, -1),
+                (-1, 0),
+                (-1, 1),
+                (0, 1),
+            ],
+        )
+    }
+}
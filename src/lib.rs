@@ -1,3 +1,10 @@
+pub mod cli;
 pub mod data;
 pub mod plugins;
+pub mod render;
+pub mod solvers;
 pub mod systems;
+pub mod tui;
+
+#[cfg(test)]
+mod properties;
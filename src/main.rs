@@ -1,32 +1,37 @@
-use bevy::app::App;
-
 use bevy::prelude::*;
-use killer_sudoku::data::cage_table::CageTable;
+use killer_sudoku::cli;
 use killer_sudoku::plugins::board_plugin::BoardPlugin;
-
-// fn main() {
-//     let mut app = App::new();
-//     app.insert_resource(WindowDescriptor {
-//         title: "sudoku".to_string(),
-//         width: 300.,
-//         height: 300.,
-//         ..Default::default()
-//     });
-//     app.add_plugins(DefaultPlugins);
-//
-//     app.add_plugin(BoardPlugin);
-//     app.add_startup_system(setup_camera);
-//     app.run();
-// }
-//
-// fn setup_camera(mut commands: Commands) {
-//     commands.spawn_bundle(OrthographicCameraBundle::new_2d());
-// }
+use killer_sudoku::plugins::picker_plugin::PickerPlugin;
+use killer_sudoku::plugins::save_plugin::SavePlugin;
+use killer_sudoku::plugins::AppState;
 
 fn main() {
-    let cage_table = CageTable::from_file("./assets/cage_table.txt");
-
-    for x in cage_table.0 {
-        println!("{:?}", x);
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        if let Err(e) = cli::run(&args) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
     }
-}
\ No newline at end of file
+
+    let mut app = App::new();
+    app.insert_resource(WindowDescriptor {
+        title: "sudoku".to_string(),
+        width: 300.,
+        height: 300.,
+        ..Default::default()
+    });
+    app.add_plugins(DefaultPlugins);
+
+    app.add_state(AppState::Picker);
+    app.add_plugin(BoardPlugin);
+    app.add_plugin(PickerPlugin);
+    app.add_plugin(SavePlugin);
+    app.add_startup_system(setup_camera);
+    app.run();
+}
+
+fn setup_camera(mut commands: Commands) {
+    commands.spawn_bundle(OrthographicCameraBundle::new_2d());
+}
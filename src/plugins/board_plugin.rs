@@ -1,87 +1,190 @@
-use crate::data::board::Board;
+use crate::data::board::{Board, CellState};
 use crate::data::cage::CageColor;
+use crate::data::game::Game;
+use crate::data::variant::{Constraint, Variant, Windoku};
+use crate::plugins::AppState;
+use crate::systems::input_handling::{handle_board_input, handle_editor_input};
 use bevy::prelude::*;
+use std::path::{Path, PathBuf};
 
 pub struct BoardPlugin;
 
 impl Plugin for BoardPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(Board::sample_puzzle())
-            .add_startup_system(setup_board);
+        app.insert_resource(Game::new(Board::sample_puzzle()))
+            .init_resource::<Cursor>()
+            .add_system_set(SystemSet::on_enter(AppState::Playing).with_system(setup_board))
+            .add_system_set(
+                SystemSet::on_update(AppState::Playing)
+                    .with_system(handle_board_input.label("input"))
+                    .with_system(update_tiles.after("input")),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Playing).with_system(despawn_board))
+            .add_system_set(SystemSet::on_enter(AppState::Editing).with_system(setup_board))
+            .add_system_set(
+                SystemSet::on_update(AppState::Editing)
+                    .with_system(handle_editor_input.label("input"))
+                    .with_system(update_tiles.after("input")),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Editing)
+                    .with_system(despawn_board)
+                    .with_system(close_editor),
+            );
     }
 }
 
-const TILE_SIZE: f32 = 1. / 9.;
+/// directory the editor saves cage layouts to
+pub const PUZZLE_DIR: &str = "./puzzles";
+
+/// fraction of the window taken by one cell
+fn tile_size(board: &Board) -> f32 {
+    1. / board.get_size() as f32
+}
+
+/// font sizes are picked for 9x9, this scales them to the actual cell size
+fn font_scale(board: &Board) -> f32 {
+    9. / board.get_size() as f32
+}
+
+/// the selected cell, and whether digits are entered as notes
+#[derive(Default)]
+pub struct Cursor {
+    pub x: usize,
+    pub y: usize,
+    pub notes_mode: bool,
+}
+
+/// the cage layout being drawn, present while editing
+pub struct Editor {
+    pub board: Board,
+    /// cells dragged over since the mouse button went down
+    pub selection: Vec<(usize, usize)>,
+    /// the cage typed digits go to, 0 before the first one is drawn
+    pub cage: usize,
+    pub path: PathBuf,
+    /// the problems of the layout by cage, worked out again when it changes
+    pub problems: Vec<(usize, String)>,
+}
+
+impl Editor {
+    /// starts from a board without cages
+    pub fn new(size: usize) -> Self {
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        Self {
+            board: Board::new(size),
+            selection: vec![],
+            cage: 0,
+            path: Path::new(PUZZLE_DIR).join(format!("cages-{}.txt", secs)),
+            problems: vec![],
+        }
+    }
+
+    /// looks for problems again after the layout or a clue changed. once every cell is in a
+    /// cage with a clue, clues that don't add up to the grid are a problem of every cage
+    pub fn check(&mut self) {
+        let board = &self.board;
+        self.problems = board.cage_problems();
+        let size = board.get_size() as i32;
+        let cages = board.get_cages().collect::<Vec<_>>();
+        let clues = cages
+            .iter()
+            .map(|(_, cells)| board.get_sum(cells[0].0, cells[0].1))
+            .collect::<Vec<_>>();
+        let covered = cages.iter().map(|(_, cells)| cells.len()).sum::<usize>();
+        let (total, expected) = (clues.iter().sum::<i32>(), size * size * (size + 1) / 2);
+        if covered == (size * size) as usize
+            && !clues.contains(&0)
+            && !board.has_operators()
+            && total != expected
+        {
+            for (index, _) in &cages {
+                let problem = format!("clues add up to {}, not {}", total, expected);
+                self.problems.push((*index, problem));
+            }
+        }
+    }
+}
 
 fn setup_board(
-    board: Res<Board>,
+    game: Res<Game>,
+    editor: Option<Res<Editor>>,
+    mut cursor: ResMut<Cursor>,
     windows: Res<Windows>,
     mut commands: Commands,
     asset_server: Res<AssetServer>,
 ) {
+    *cursor = Cursor::default();
+    let board = match &editor {
+        Some(editor) => &editor.board,
+        None => game.get_board(),
+    };
     let window = windows.primary();
+    let (box_width, box_height) = board.get_box_size();
+    let (box_columns, box_rows) = (board.get_size() / box_width, board.get_size() / box_height);
+    let (tile_size, font_scale) = (tile_size(board), font_scale(board));
     commands
         .spawn()
         .insert(Name::new("Board"))
+        .insert(BoardComponent)
         .insert(Transform::from_xyz(
-            window.width() / 2.,
+            -window.width() / 2.,
             window.height() / 2.,
             0.,
         ))
         .insert(GlobalTransform::default())
         .with_children(|parent| {
-            for section_x in 0..3 {
-                for section_y in 0..3 {
+            for section_x in 0..box_columns {
+                for section_y in 0..box_rows {
                     parent
                         .spawn()
                         .insert(GlobalTransform::default())
                         .insert(Transform {
                             translation: Vec3::new(
-                                3. * -(section_x as f32) * TILE_SIZE * window.width(),
-                                3. * -(section_y as f32) * TILE_SIZE * window.height(),
+                                (box_width * section_x) as f32 * tile_size * window.width(),
+                                -((box_height * section_y) as f32) * tile_size * window.height(),
                                 0.,
                             ),
                             scale: Vec3::splat((window.height() - 5.) / window.height()),
                             ..default()
                         })
                         .with_children(|parent| {
-                            for x in 0..3 {
-                                for y in 0..3 {
-                                    let (global_x, global_y) = (
-                                        (section_x as usize * 3) + x,
-                                        (section_y as usize * 3) + y,
-                                    );
-                                    let number = board.get_value(global_x, global_y);
-                                    let sum = board.get_sum(global_x, global_y);
-
-                                    let color = match board.get_color(global_x, global_y) {
-                                        CageColor::Green => Color::rgba(0.8, 0.9, 0.7, 1.),
-                                        CageColor::Blue => Color::rgba(0.8, 0.9, 1., 1.),
-                                        CageColor::Yellow => Color::rgba(1., 1., 0.7, 1.),
-                                        CageColor::Red => Color::rgba(1., 0.8, 0.9, 1.),
-                                    };
-                                    parent.spawn_bundle(SpriteBundle {
-                                        sprite: Sprite {
-                                            color,
-                                            custom_size: Some(Vec2::splat(
-                                                TILE_SIZE * window.height() - 1.,
-                                            )),
+                            for x in 0..box_width {
+                                for y in 0..box_height {
+                                    let (global_x, global_y) =
+                                        ((section_x * box_width) + x, (section_y * box_height) + y);
+                                    let label = cage_label(board, global_x, global_y);
+
+                                    parent
+                                        .spawn_bundle(SpriteBundle {
+                                            sprite: Sprite {
+                                                color: cage_color(board, global_x, global_y),
+                                                custom_size: Some(Vec2::splat(
+                                                    tile_size * window.height() - 1.,
+                                                )),
+                                                ..default()
+                                            },
+                                            transform: Transform::from_xyz(
+                                                (x as f32) * tile_size * window.width()
+                                                    + tile_size * window.width() / 2.,
+                                                -(y as f32) * tile_size * window.height()
+                                                    - tile_size * window.height() / 2.,
+                                                1.,
+                                            ),
                                             ..default()
-                                        },
-                                        transform: Transform::from_xyz(
-                                            -(x as f32) * TILE_SIZE * window.width()
-                                                - TILE_SIZE * window.width() / 2.,
-                                            -(y as f32) * TILE_SIZE * window.height()
-                                                - TILE_SIZE * window.height() / 2.,
-                                            1.,
-                                        ),
-                                        ..default()
-                                    });
+                                        })
+                                        .insert(TileComponent {
+                                            x: global_x,
+                                            y: global_y,
+                                        });
                                     let transform = Transform::from_xyz(
-                                        -(x as f32) * TILE_SIZE * window.width()
-                                            - TILE_SIZE * window.width() / 2.,
-                                        -(y as f32) * TILE_SIZE * window.height()
-                                            - TILE_SIZE * window.height() / 2.,
+                                        (x as f32) * tile_size * window.width()
+                                            + tile_size * window.width() / 2.,
+                                        -(y as f32) * tile_size * window.height()
+                                            - tile_size * window.height() / 2.,
                                         2.,
                                     );
                                     let style = TextStyle {
@@ -89,38 +192,17 @@ fn setup_board(
                                         color: Color::BLACK,
                                         ..default()
                                     };
-                                    parent.spawn_bundle(Text2dBundle {
-                                        text: Text {
-                                            alignment: TextAlignment {
-                                                horizontal: HorizontalAlign::Right,
-                                                vertical: VerticalAlign::Bottom,
-                                            },
-                                            sections: vec![TextSection {
-                                                value: sum.to_string(),
-                                                style: TextStyle {
-                                                    font_size: 12.,
-                                                    ..style.clone()
-                                                },
-                                            }],
-                                        },
-                                        transform,
-                                        ..default()
-                                    });
                                     parent
                                         .spawn_bundle(Text2dBundle {
                                             text: Text {
                                                 alignment: TextAlignment {
-                                                    horizontal: HorizontalAlign::Center,
-                                                    vertical: VerticalAlign::Center,
+                                                    horizontal: HorizontalAlign::Right,
+                                                    vertical: VerticalAlign::Bottom,
                                                 },
                                                 sections: vec![TextSection {
-                                                    value: if number != 0 {
-                                                        number.to_string()
-                                                    } else {
-                                                        "".to_string()
-                                                    },
+                                                    value: label,
                                                     style: TextStyle {
-                                                        font_size: 24.,
+                                                        font_size: 12. * font_scale,
                                                         ..style.clone()
                                                     },
                                                 }],
@@ -128,7 +210,51 @@ fn setup_board(
                                             transform,
                                             ..default()
                                         })
-                                        .insert(NumberComponent);
+                                        .insert(LabelComponent {
+                                            x: global_x,
+                                            y: global_y,
+                                        });
+                                    parent
+                                        .spawn_bundle(Text2dBundle {
+                                            text: Text::with_section(
+                                                "",
+                                                TextStyle {
+                                                    font_size: 9. * font_scale,
+                                                    color: Color::DARK_GRAY,
+                                                    ..style.clone()
+                                                },
+                                                TextAlignment {
+                                                    horizontal: HorizontalAlign::Center,
+                                                    vertical: VerticalAlign::Center,
+                                                },
+                                            ),
+                                            transform,
+                                            ..default()
+                                        })
+                                        .insert(NotesComponent {
+                                            x: global_x,
+                                            y: global_y,
+                                        });
+                                    parent
+                                        .spawn_bundle(Text2dBundle {
+                                            text: Text::with_section(
+                                                "",
+                                                TextStyle {
+                                                    font_size: 24. * font_scale,
+                                                    ..style.clone()
+                                                },
+                                                TextAlignment {
+                                                    horizontal: HorizontalAlign::Center,
+                                                    vertical: VerticalAlign::Center,
+                                                },
+                                            ),
+                                            transform,
+                                            ..default()
+                                        })
+                                        .insert(NumberComponent {
+                                            x: global_x,
+                                            y: global_y,
+                                        });
                                 }
                             }
                         });
@@ -137,5 +263,184 @@ fn setup_board(
         });
 }
 
+/// the clue of the cage, with its operator symbol when the puzzle uses more than sums
+fn cage_label(board: &Board, x: usize, y: usize) -> String {
+    let op = board.get_operator(x, y);
+    match board.get_sum(x, y) {
+        0 => "".to_string(),
+        sum if board.has_operators() => format!("{}{}", sum, op.label()),
+        sum => sum.to_string(),
+    }
+}
+
+fn cage_color(board: &Board, x: usize, y: usize) -> Color {
+    let color = match board.get_color(x, y) {
+        CageColor::Green => Color::rgba(0.8, 0.9, 0.7, 1.),
+        CageColor::Blue => Color::rgba(0.8, 0.9, 1., 1.),
+        CageColor::Yellow => Color::rgba(1., 1., 0.7, 1.),
+        CageColor::Red => Color::rgba(1., 0.8, 0.9, 1.),
+    };
+    if in_variant_region(board, x, y) {
+        color * 0.85
+    } else {
+        color
+    }
+}
+
+/// givens in black so they stand out from the player's entries
+fn number_color(state: CellState) -> Color {
+    match state {
+        CellState::Player => Color::rgb(0.1, 0.3, 0.7),
+        CellState::Solver => Color::DARK_GRAY,
+        _ => Color::BLACK,
+    }
+}
+
+/// whether (x, y) lies on a diagonal or in a windoku region of the puzzle's variants
+fn in_variant_region(board: &Board, x: usize, y: usize) -> bool {
+    board.get_variants().iter().any(|variant| match variant {
+        Variant::Diagonal => x == y || x + y == board.get_size() - 1,
+        Variant::Windoku => !Windoku.peers(board, x, y).is_empty(),
+        _ => false,
+    })
+}
+
+/// keeps the spawned tiles in sync with the `Game` and `Cursor` resources, or the `Editor`
+/// while editing
+#[allow(clippy::type_complexity)]
+fn update_tiles(
+    game: Res<Game>,
+    editor: Option<Res<Editor>>,
+    cursor: Res<Cursor>,
+    mut tiles: Query<(&mut Sprite, &TileComponent)>,
+    mut numbers: Query<
+        (&mut Text, &NumberComponent),
+        (Without<NotesComponent>, Without<LabelComponent>),
+    >,
+    mut notes: Query<
+        (&mut Text, &NotesComponent),
+        (Without<NumberComponent>, Without<LabelComponent>),
+    >,
+    mut labels: Query<
+        (&mut Text, &LabelComponent),
+        (Without<NumberComponent>, Without<NotesComponent>),
+    >,
+) {
+    let board = match &editor {
+        Some(editor) => &editor.board,
+        None => game.get_board(),
+    };
+    for (mut sprite, tile) in tiles.iter_mut() {
+        let mut color = cage_color(board, tile.x, tile.y);
+        if let Some(editor) = &editor {
+            let index = board.get_cage_index(tile.x, tile.y);
+            if editor.selection.contains(&(tile.x, tile.y)) || (index != 0 && index == editor.cage)
+            {
+                color = Color::rgb(0.7, 0.7, 0.7);
+            } else if editor.problems.iter().any(|(i, _)| *i == index) {
+                color = Color::rgb(0.9, 0.4, 0.4);
+            }
+        } else if (tile.x, tile.y) == (cursor.x, cursor.y) {
+            color = if cursor.notes_mode {
+                Color::rgb(0.7, 0.7, 0.9)
+            } else {
+                Color::rgb(0.7, 0.7, 0.7)
+            };
+        }
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+    for (mut text, number) in numbers.iter_mut() {
+        let value = match board.get_value(number.x, number.y) {
+            0 => "".to_string(),
+            n => n.to_string(),
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+        let color = number_color(board.get_state(number.x, number.y));
+        if text.sections[0].style.color != color {
+            text.sections[0].style.color = color;
+        }
+    }
+    for (mut text, label) in labels.iter_mut() {
+        let value = cage_label(board, label.x, label.y);
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+    for (mut text, note) in notes.iter_mut() {
+        let value = if editor.is_none() && board.get_value(note.x, note.y) == 0 {
+            format_notes(game.get_notes(note.x, note.y), board.get_box_size().0)
+        } else {
+            "".to_string()
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
+
+/// lays out notes in rows of `width` digits, so every digit keeps its place
+fn format_notes(notes: &[i32], width: usize) -> String {
+    if notes.is_empty() {
+        return "".to_string();
+    }
+    let width = width as i32;
+    let rows = (notes.iter().max().unwrap() + width - 1) / width;
+    (0..rows)
+        .map(|row| {
+            (1..=width)
+                .map(|col| {
+                    let num = row * width + col;
+                    if notes.contains(&num) {
+                        num.to_string()
+                    } else {
+                        " ".to_string()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn close_editor(mut commands: Commands, mut windows: ResMut<Windows>) {
+    commands.remove_resource::<Editor>();
+    windows.primary_mut().set_title("sudoku".to_string());
+}
+
+fn despawn_board(mut commands: Commands, boards: Query<Entity, With<BoardComponent>>) {
+    for entity in boards.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[derive(Component)]
+struct BoardComponent;
+
 #[derive(Component)]
-struct NumberComponent;
+struct TileComponent {
+    x: usize,
+    y: usize,
+}
+
+#[derive(Component)]
+struct NumberComponent {
+    x: usize,
+    y: usize,
+}
+
+#[derive(Component)]
+struct NotesComponent {
+    x: usize,
+    y: usize,
+}
+
+#[derive(Component)]
+struct LabelComponent {
+    x: usize,
+    y: usize,
+}
@@ -1 +1,12 @@
 pub mod board_plugin;
+pub mod picker_plugin;
+pub mod save_plugin;
+
+/// the picker is shown on startup, choosing a puzzle switches to playing it and choosing a
+/// cage layout to editing it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AppState {
+    Picker,
+    Playing,
+    Editing,
+}
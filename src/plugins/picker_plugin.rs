@@ -0,0 +1,182 @@
+use crate::data::board::{Board, Difficulty};
+use crate::data::game::{list_saves, new_save_path, Game};
+use crate::plugins::board_plugin::Editor;
+use crate::plugins::save_plugin::SaveSlot;
+use crate::plugins::AppState;
+use bevy::prelude::*;
+use std::path::PathBuf;
+
+/// Startup menu listing saved games to resume, followed by options for a new puzzle.
+pub struct PickerPlugin;
+
+impl Plugin for PickerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::Picker).with_system(setup_picker))
+            .add_system_set(SystemSet::on_update(AppState::Picker).with_system(handle_picker_input))
+            .add_system_set(SystemSet::on_exit(AppState::Picker).with_system(despawn_picker));
+    }
+}
+
+enum PickerOption {
+    Resume(PathBuf, Box<Game>),
+    Sample,
+    Archive,
+    Random(usize, Difficulty),
+    /// draws the cages of a new puzzle
+    Edit(usize),
+}
+
+impl PickerOption {
+    fn label(&self) -> String {
+        match self {
+            PickerOption::Resume(path, game) => {
+                let name = path.file_stem().unwrap_or_default().to_string_lossy();
+                let secs = game.elapsed as u64;
+                format!("resume {} ({}:{:02})", name, secs / 60, secs % 60)
+            }
+            PickerOption::Sample => "new: sample puzzle".to_string(),
+            PickerOption::Archive => "new: archive puzzle".to_string(),
+            PickerOption::Random(size, difficulty) => {
+                let difficulty = match difficulty {
+                    Difficulty::Easy => "easy",
+                    Difficulty::Normal => "normal",
+                    Difficulty::Hard => "hard",
+                };
+                format!("new: random {}x{} ({})", size, size, difficulty)
+            }
+            PickerOption::Edit(size) => format!("edit: new {}x{} cage layout", size, size),
+        }
+    }
+}
+
+struct Picker {
+    options: Vec<PickerOption>,
+    selected: usize,
+}
+
+#[derive(Component)]
+struct PickerComponent;
+
+fn setup_picker(mut commands: Commands, asset_server: Res<AssetServer>, windows: Res<Windows>) {
+    let mut options = list_saves()
+        .into_iter()
+        .filter_map(|path| match Game::load(&path) {
+            Ok(game) if !game.is_finished() => Some(PickerOption::Resume(path, Box::new(game))),
+            Ok(_) => None,
+            Err(e) => {
+                warn!("skipping save {:?}: {}", path, e);
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    options.extend([
+        PickerOption::Sample,
+        PickerOption::Archive,
+        PickerOption::Random(9, Difficulty::Easy),
+        PickerOption::Random(9, Difficulty::Normal),
+        PickerOption::Random(9, Difficulty::Hard),
+        PickerOption::Random(4, Difficulty::Normal),
+        PickerOption::Random(6, Difficulty::Normal),
+        PickerOption::Random(16, Difficulty::Normal),
+        PickerOption::Edit(9),
+        PickerOption::Edit(6),
+    ]);
+    let picker = Picker {
+        options,
+        selected: 0,
+    };
+
+    let window = windows.primary();
+    let style = TextStyle {
+        font: asset_server.load("font.ttf"),
+        font_size: 16.,
+        color: Color::WHITE,
+    };
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text {
+                sections: picker_sections(&picker, &style),
+                alignment: TextAlignment {
+                    horizontal: HorizontalAlign::Left,
+                    vertical: VerticalAlign::Top,
+                },
+            },
+            transform: Transform::from_xyz(
+                -window.width() / 2. + 10.,
+                window.height() / 2. - 10.,
+                0.,
+            ),
+            ..default()
+        })
+        .insert(PickerComponent);
+    commands.insert_resource(picker);
+}
+
+fn picker_sections(picker: &Picker, style: &TextStyle) -> Vec<TextSection> {
+    picker
+        .options
+        .iter()
+        .enumerate()
+        .map(|(i, option)| {
+            let marker = if i == picker.selected { "> " } else { "  " };
+            TextSection {
+                value: format!("{}{}\n", marker, option.label()),
+                style: style.clone(),
+            }
+        })
+        .collect()
+}
+
+fn handle_picker_input(
+    keys: Res<Input<KeyCode>>,
+    picker: Option<ResMut<Picker>>,
+    mut game: ResMut<Game>,
+    mut slot: ResMut<SaveSlot>,
+    mut state: ResMut<State<AppState>>,
+    mut texts: Query<&mut Text, With<PickerComponent>>,
+    mut commands: Commands,
+) {
+    let mut picker = match picker {
+        Some(picker) => picker,
+        None => return,
+    };
+    if keys.just_pressed(KeyCode::Up) && picker.selected > 0 {
+        picker.selected -= 1;
+    }
+    if keys.just_pressed(KeyCode::Down) && picker.selected + 1 < picker.options.len() {
+        picker.selected += 1;
+    }
+    if picker.is_changed() {
+        for mut text in texts.iter_mut() {
+            let style = text.sections[0].style.clone();
+            text.sections = picker_sections(&picker, &style);
+        }
+    }
+
+    if keys.just_pressed(KeyCode::Return) {
+        let (new_game, path) = match &picker.options[picker.selected] {
+            PickerOption::Resume(path, game) => (*game.clone(), path.clone()),
+            PickerOption::Sample => (Game::new(Board::sample_puzzle()), new_save_path()),
+            PickerOption::Archive => (Game::new(Board::get_from_archive()), new_save_path()),
+            PickerOption::Random(size, difficulty) => (
+                Game::new(Board::gen_random_sized(*size, *difficulty)),
+                new_save_path(),
+            ),
+            PickerOption::Edit(size) => {
+                commands.insert_resource(Editor::new(*size));
+                state.set(AppState::Editing).unwrap();
+                return;
+            }
+        };
+        *game = new_game;
+        slot.0 = path;
+        state.set(AppState::Playing).unwrap();
+    }
+}
+
+fn despawn_picker(mut commands: Commands, pickers: Query<Entity, With<PickerComponent>>) {
+    for entity in pickers.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<Picker>();
+}
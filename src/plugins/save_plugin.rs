@@ -0,0 +1,69 @@
+use crate::data::game::{new_save_path, Game};
+use crate::plugins::AppState;
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::window::WindowCloseRequested;
+use std::path::PathBuf;
+
+/// Keeps the in-progress `Game` on disk: every few seconds, when leaving the board and when
+/// the window closes.
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SaveSlot(new_save_path()))
+            .insert_resource(AutosaveTimer(Timer::from_seconds(AUTOSAVE_SECONDS, true)))
+            .add_system_set(
+                SystemSet::on_update(AppState::Playing)
+                    .with_system(track_time)
+                    .with_system(autosave)
+                    .with_system(save_on_close),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Playing).with_system(save_game));
+    }
+}
+
+const AUTOSAVE_SECONDS: f32 = 10.;
+
+/// the file the current game is saved to
+pub struct SaveSlot(pub PathBuf);
+
+struct AutosaveTimer(Timer);
+
+fn track_time(time: Res<Time>, mut game: ResMut<Game>) {
+    if !game.is_finished() {
+        game.elapsed += time.delta_seconds_f64();
+    }
+}
+
+fn autosave(
+    time: Res<Time>,
+    mut timer: ResMut<AutosaveTimer>,
+    game: Res<Game>,
+    slot: Res<SaveSlot>,
+) {
+    if timer.0.tick(time.delta()).just_finished() {
+        save(&game, &slot);
+    }
+}
+
+fn save_on_close(
+    mut close_requests: EventReader<WindowCloseRequested>,
+    mut exits: EventReader<AppExit>,
+    game: Res<Game>,
+    slot: Res<SaveSlot>,
+) {
+    if close_requests.iter().count() + exits.iter().count() > 0 {
+        save(&game, &slot);
+    }
+}
+
+fn save_game(game: Res<Game>, slot: Res<SaveSlot>) {
+    save(&game, &slot);
+}
+
+fn save(game: &Game, slot: &SaveSlot) {
+    if let Err(e) = game.save(&slot.0) {
+        error!("could not save game to {:?}: {}", slot.0, e);
+    }
+}
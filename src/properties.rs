@@ -0,0 +1,231 @@
+//! Property tests over random boards: solved grids of every box shape, cut into random cages
+//! and partly revealed, written in the puzzle format so they go through `Board::parse` too.
+
+use crate::data::board::{Board, MAX_SIZE};
+use crate::data::cage_table::CageTable;
+use crate::data::format;
+use crate::solvers::backtracking::Backtracking;
+use crate::solvers::sat::Sat;
+use proptest::prelude::*;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+/// a random solved grid of `size` with boxes of `box_width` x `box_height`: a fixed pattern
+/// with its digits, bands, stacks and the rows and columns inside them shuffled
+fn solved_grid(
+    size: usize,
+    box_width: usize,
+    box_height: usize,
+    rng: &mut StdRng,
+) -> Vec<Vec<i32>> {
+    let mut digits = (1..=size as i32).collect::<Vec<_>>();
+    digits.shuffle(rng);
+    // rows are shuffled inside bands of `box_height`, columns inside stacks of `box_width`
+    let shuffled = |group: usize, rng: &mut StdRng| {
+        let mut groups = (0..size / group).collect::<Vec<_>>();
+        groups.shuffle(rng);
+        groups
+            .into_iter()
+            .flat_map(|g| {
+                let mut lines = (g * group..(g + 1) * group).collect::<Vec<_>>();
+                lines.shuffle(rng);
+                lines
+            })
+            .collect::<Vec<_>>()
+    };
+    let rows = shuffled(box_height, rng);
+    let columns = shuffled(box_width, rng);
+    rows.iter()
+        .map(|y| {
+            columns
+                .iter()
+                .map(|x| digits[(box_width * (y % box_height) + y / box_height + x) % size])
+                .collect()
+        })
+        .collect()
+}
+
+/// cage indices for every cell: cages of up to 4 orthogonally connected cells with different
+/// digits in `grid`
+fn cage_partition(grid: &[Vec<i32>], rng: &mut StdRng) -> Vec<Vec<i32>> {
+    let size = grid.len();
+    let mut cages = vec![vec![0; size]; size];
+    let mut order = (0..size * size).collect::<Vec<_>>();
+    order.shuffle(rng);
+    let mut index = 0;
+    for start in order {
+        let (x, y) = (start % size, start / size);
+        if cages[y][x] != 0 {
+            continue;
+        }
+        index += 1;
+        cages[y][x] = index;
+        let mut cells = vec![(x, y)];
+        let target = rng.gen_range(1..=4);
+        while cells.len() < target {
+            let mut next = vec![];
+            for (cx, cy) in &cells {
+                let around = [
+                    (cx.wrapping_sub(1), *cy),
+                    (cx + 1, *cy),
+                    (*cx, cy.wrapping_sub(1)),
+                    (*cx, cy + 1),
+                ];
+                next.extend(around.into_iter().filter(|(nx, ny)| {
+                    *nx < size
+                        && *ny < size
+                        && cages[*ny][*nx] == 0
+                        && !cells
+                            .iter()
+                            .any(|(ox, oy)| grid[*oy][*ox] == grid[*ny][*nx])
+                }));
+            }
+            match next.choose(rng) {
+                Some((nx, ny)) => {
+                    cages[*ny][*nx] = index;
+                    cells.push((*nx, *ny));
+                }
+                None => break,
+            }
+        }
+    }
+    cages
+}
+
+/// a puzzle in the text format with the cages of a random solved grid, each digit given with
+/// chance `reveal`
+fn puzzle_text(size: usize, seed: u64, reveal: f64) -> String {
+    let board = Board::new(size);
+    let (box_width, box_height) = board.get_box_size();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let solution = solved_grid(size, box_width, box_height, &mut rng);
+    let cages = cage_partition(&solution, &mut rng);
+    let givens = solution
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|n| if rng.gen_bool(reveal) { *n } else { 0 })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut out = format!("size {} {} {}\n", size, box_width, box_height);
+    format::write_grid(&mut out, "grid", &givens);
+    format::write_grid(&mut out, "solution", &solution);
+    format::write_grid(&mut out, "cages", &cages);
+    out.push_str("sums\n");
+    let count = cages.iter().flatten().max().copied().unwrap_or(0);
+    for index in 1..=count {
+        let sum = (0..size * size)
+            .filter(|i| cages[i / size][i % size] == index)
+            .map(|i| solution[i / size][i % size])
+            .sum::<i32>();
+        out.push_str(&format!("{} {}\n", index, sum));
+    }
+    out
+}
+
+fn puzzle(reveal: std::ops::Range<f64>) -> impl Strategy<Value = String> {
+    (prop_oneof![Just(4), Just(6), Just(9)], any::<u64>(), reveal)
+        .prop_map(|(size, seed, reveal)| puzzle_text(size, seed, reveal))
+}
+
+/// whether `board` can hold `num` at (x, y) by its row, column and box alone
+fn in_houses(board: &Board, x: usize, y: usize, num: i32) -> bool {
+    let size = board.get_size();
+    let (box_width, box_height) = board.get_box_size();
+    let (box_x, box_y) = (x / box_width * box_width, y / box_height * box_height);
+    (0..size).any(|o| {
+        board.get_value(o, y) == num
+            || board.get_value(x, o) == num
+            || board.get_value(box_x + o % box_width, box_y + o / box_width) == num
+    })
+}
+
+/// whether every cage of a full `board` meets its clue
+fn cages_met(board: &Board) -> bool {
+    let size = board.get_size();
+    (0..size * size).all(|i| {
+        let (x, y) = (i % size, i / size);
+        board.get_cage_index(x, y) == 0
+            || board
+                .get_cage_cells(x, y)
+                .iter()
+                .map(|(cx, cy)| board.get_value(*cx, *cy))
+                .sum::<i32>()
+                == board.get_sum(x, y)
+    })
+}
+
+proptest! {
+    #[test]
+    fn check_parse_serialize_roundtrip(text in puzzle(0.0..1.0)) {
+        let board = Board::parse(&text).unwrap();
+        prop_assert_eq!(Board::parse(&board.serialize()), Ok(board));
+    }
+
+    #[test]
+    fn check_is_possible_rejects_duplicates(text in puzzle(0.2..0.8)) {
+        let board = Board::parse(&text).unwrap();
+        let size = board.get_size();
+        for i in 0..size * size {
+            let (x, y) = (i % size, i / size);
+            for num in 1..=size as i32 {
+                if board.get_value(x, y) == 0 && in_houses(&board, x, y, num) {
+                    prop_assert!(!board.is_possible(x, y, num), "{} at ({}, {})", num, x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn check_parsers_never_panic(text in "\\PC*") {
+        let _ = Board::parse(&text);
+        let _ = CageTable::parse(&text);
+    }
+
+    #[test]
+    fn check_parse_rejects_bad_sizes(
+        size in 0..usize::MAX,
+        width in 0..usize::MAX,
+        height in 0..usize::MAX,
+    ) {
+        let boxes = format!("size {} {} {}", size, width, height);
+        let valid = (1..=MAX_SIZE).contains(&size) && width.checked_mul(height) == Some(size);
+        prop_assert_eq!(Board::parse(&boxes).is_ok(), valid);
+        let square = format!("size {}", size);
+        prop_assert_eq!(Board::parse(&square).is_ok(), (1..=MAX_SIZE).contains(&size));
+    }
+
+    #[test]
+    fn check_parse_never_panics_on_near_puzzles(
+        text in "(size|grid|solution|cages|sums|variants) ?[0-9a-z*+/?-]{0,6}(\n[ 0-9*+/?-]{0,24}){0,12}"
+    ) {
+        if let Ok(board) = Board::parse(&text) {
+            prop_assert_eq!(Board::parse(&board.serialize()), Ok(board));
+        }
+    }
+}
+
+proptest! {
+    // solving is slower, fewer cases still cover every box shape
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn check_solutions_are_valid(text in puzzle(0.3..0.7)) {
+        let board = Board::parse(&text).unwrap();
+        for solution in [Backtracking::new().solve(&board), Sat::new().solve(&board)] {
+            // the generated grid solves it, so there is always a solution
+            let solution = solution.unwrap();
+            prop_assert!(solution.is_finished());
+            prop_assert!(cages_met(&solution));
+            let size = board.get_size();
+            for i in 0..size * size {
+                let (x, y) = (i % size, i / size);
+                let given = board.get_value(x, y);
+                prop_assert!(given == 0 || solution.get_value(x, y) == given);
+            }
+        }
+    }
+}
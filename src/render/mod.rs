@@ -0,0 +1,422 @@
+//! Draws boards without a window, as SVG, PNG or PDF, so puzzles can be printed.
+//!
+//! A board is turned into a `Scene` of rectangles, lines and text first, the output formats
+//! only have to know how to draw those.
+
+use crate::data::board::Board;
+use crate::solvers::deduction::Candidates;
+
+pub mod pdf;
+pub mod raster;
+pub mod svg;
+
+/// an A4 page in CSS pixels, 96 per inch
+pub const A4: (f32, f32) = (793.7, 1122.5);
+
+/// space around the puzzles of a page
+const PAGE_MARGIN: f32 = 48.;
+
+pub type Rgb = [u8; 3];
+
+pub const BLACK: Rgb = [0, 0, 0];
+pub const WHITE: Rgb = [255, 255, 255];
+const CAGE_COLOR: Rgb = [70, 70, 70];
+const SOLUTION_COLOR: Rgb = [60, 90, 180];
+const CANDIDATE_COLOR: Rgb = [110, 110, 110];
+
+/// where a text's x lies
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Anchor {
+    Start,
+    Middle,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Rect {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        color: Rgb,
+    },
+    /// the rasteriser only draws horizontal and vertical lines, which is all a board needs
+    Line {
+        from: (f32, f32),
+        to: (f32, f32),
+        width: f32,
+        color: Rgb,
+        dashed: bool,
+    },
+    /// `y` is the baseline
+    Text {
+        x: f32,
+        y: f32,
+        size: f32,
+        text: String,
+        color: Rgb,
+        anchor: Anchor,
+    },
+}
+
+/// shapes on a white page of `width` x `height` pixels
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scene {
+    pub width: f32,
+    pub height: f32,
+    pub shapes: Vec<Shape>,
+}
+
+/// what to write in the empty cells
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Overlay {
+    Nothing,
+    /// the solution stored with the puzzle
+    Solution,
+    /// the digits each cell can still hold
+    Candidates,
+}
+
+impl Overlay {
+    pub const ALL: [Overlay; 3] = [Overlay::Nothing, Overlay::Solution, Overlay::Candidates];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Overlay::Nothing => "none",
+            Overlay::Solution => "solution",
+            Overlay::Candidates => "candidates",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|o| o.name() == name).copied()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderOptions {
+    /// pixels per cell when a puzzle gets a page of its own size
+    pub cell: f32,
+    pub overlay: Overlay,
+    /// puzzles per A4 page, 0 gives every puzzle a page of its own size
+    pub per_page: usize,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RenderOptions {
+    pub fn new() -> Self {
+        Self {
+            cell: 40.,
+            overlay: Overlay::Nothing,
+            per_page: 0,
+        }
+    }
+    pub fn with_cell(mut self, cell: f32) -> Self {
+        self.cell = cell;
+        self
+    }
+    pub fn with_overlay(mut self, overlay: Overlay) -> Self {
+        self.overlay = overlay;
+        self
+    }
+    pub fn with_per_page(mut self, per_page: usize) -> Self {
+        self.per_page = per_page;
+        self
+    }
+}
+
+/// one page per puzzle, each titled by its name unless that's empty
+pub fn render(name: &str, board: &Board, options: &RenderOptions) -> Scene {
+    let margin = options.cell / 2.;
+    let title = if name.is_empty() { 0. } else { options.cell };
+    let side = board.get_size() as f32 * options.cell;
+    let mut shapes = vec![];
+    if !name.is_empty() {
+        shapes.push(title_text(name, margin, margin + title * 0.6, title * 0.5));
+    }
+    draw_board(
+        &mut shapes,
+        board,
+        options.overlay,
+        (margin, margin + title),
+        options.cell,
+    );
+    Scene {
+        width: side + 2. * margin,
+        height: side + 2. * margin + title,
+        shapes,
+    }
+}
+
+/// the puzzles laid out `options.per_page` to an A4 page, in as many columns as fit squarely
+pub fn render_pages(puzzles: &[(String, Board)], options: &RenderOptions) -> Vec<Scene> {
+    if options.per_page == 0 {
+        return puzzles
+            .iter()
+            .map(|(name, board)| render(name, board, options))
+            .collect();
+    }
+    let columns = ((options.per_page as f32).sqrt() as usize).max(1);
+    let rows = options.per_page.div_ceil(columns);
+    let slot = (
+        (A4.0 - 2. * PAGE_MARGIN) / columns as f32,
+        (A4.1 - 2. * PAGE_MARGIN) / rows as f32,
+    );
+    puzzles
+        .chunks(options.per_page)
+        .map(|page| {
+            let mut shapes = vec![];
+            for (i, (name, board)) in page.iter().enumerate() {
+                let (x, y) = (
+                    PAGE_MARGIN + (i % columns) as f32 * slot.0,
+                    PAGE_MARGIN + (i / columns) as f32 * slot.1,
+                );
+                // a title line and some space between the puzzles
+                let title = slot.1 / 12.;
+                let side = (slot.0 * 0.9).min(slot.1 - title * 1.5);
+                let left = x + (slot.0 - side) / 2.;
+                shapes.push(title_text(name, left, y + title * 0.7, title * 0.45));
+                let cell = side / board.get_size() as f32;
+                draw_board(&mut shapes, board, options.overlay, (left, y + title), cell);
+            }
+            Scene {
+                width: A4.0,
+                height: A4.1,
+                shapes,
+            }
+        })
+        .collect()
+}
+
+/// a booklet: the puzzles first, then their solutions in the same order
+pub fn render_booklet(puzzles: &[(String, Board)], options: &RenderOptions) -> Vec<Scene> {
+    let solutions = puzzles
+        .iter()
+        .map(|(name, board)| (format!("{} - solution", name), board.clone()))
+        .collect::<Vec<_>>();
+    let mut pages = render_pages(puzzles, &options.clone().with_overlay(Overlay::Nothing));
+    pages.extend(render_pages(
+        &solutions,
+        &options.clone().with_overlay(Overlay::Solution),
+    ));
+    pages
+}
+
+fn title_text(name: &str, x: f32, y: f32, size: f32) -> Shape {
+    Shape::Text {
+        x,
+        y,
+        size,
+        text: name.to_string(),
+        color: BLACK,
+        anchor: Anchor::Start,
+    }
+}
+
+/// draws `board` with its top left corner at `origin`
+fn draw_board(
+    shapes: &mut Vec<Shape>,
+    board: &Board,
+    overlay: Overlay,
+    origin: (f32, f32),
+    cell: f32,
+) {
+    let size = board.get_size();
+    let (box_width, box_height) = board.get_box_size();
+    let at = |x: usize, y: usize| (origin.0 + x as f32 * cell, origin.1 + y as f32 * cell);
+
+    draw_cages(shapes, board, origin, cell);
+
+    let candidates = match overlay {
+        Overlay::Candidates => Some(Candidates::new(board)),
+        _ => None,
+    };
+    for y in 0..size {
+        for x in 0..size {
+            let (left, top) = at(x, y);
+            let (num, color) = match (board.get_value(x, y), overlay) {
+                (0, Overlay::Solution) => (board.get_solution()[y][x], SOLUTION_COLOR),
+                (num, _) => (num, BLACK),
+            };
+            if num != 0 {
+                shapes.push(Shape::Text {
+                    x: left + cell / 2.,
+                    y: top + cell * 0.72,
+                    size: cell * 0.6,
+                    text: num.to_string(),
+                    color,
+                    anchor: Anchor::Middle,
+                });
+            } else if let Some(candidates) = &candidates {
+                // every digit keeps its place in a box-shaped grid, like the notes in the app,
+                // inside the cage outline and below the clue
+                let step = (
+                    cell * 0.7 / box_width as f32,
+                    cell * 0.6 / box_height as f32,
+                );
+                for digit in candidates.get(x, y) {
+                    let i = digit as usize - 1;
+                    shapes.push(Shape::Text {
+                        x: left + cell * 0.15 + ((i % box_width) as f32 + 0.5) * step.0,
+                        y: top + cell * 0.28 + ((i / box_width) as f32 + 0.8) * step.1,
+                        size: step.0.min(step.1) * 0.8,
+                        text: digit.to_string(),
+                        color: CANDIDATE_COLOR,
+                        anchor: Anchor::Middle,
+                    });
+                }
+            }
+        }
+    }
+
+    // thin lines between cells, thick ones around boxes and the board
+    for i in 0..=size {
+        let thick = i % box_width == 0;
+        let (x, top) = at(i, 0);
+        let (_, bottom) = at(i, size);
+        shapes.push(grid_line((x, top), (x, bottom), cell, thick));
+        let thick = i % box_height == 0;
+        let (left, y) = at(0, i);
+        let (right, _) = at(size, i);
+        shapes.push(grid_line((left, y), (right, y), cell, thick));
+    }
+}
+
+fn grid_line(from: (f32, f32), to: (f32, f32), cell: f32, thick: bool) -> Shape {
+    Shape::Line {
+        from,
+        to,
+        width: if thick { cell / 16. } else { cell / 48. },
+        color: BLACK,
+        dashed: false,
+    }
+}
+
+/// dashed outlines just inside the border of every cage, with the clue in its first cell
+fn draw_cages(shapes: &mut Vec<Shape>, board: &Board, origin: (f32, f32), cell: f32) {
+    let size = board.get_size() as i32;
+    let inset = cell * 0.1;
+    let index = |x: i32, y: i32| {
+        if x < 0 || y < 0 || x >= size || y >= size {
+            0
+        } else {
+            board.get_cage_index(x as usize, y as usize)
+        }
+    };
+    for (cage, cells) in board.get_cages() {
+        let same = |x: i32, y: i32| index(x, y) == cage;
+        for (x, y) in cells.iter().map(|(x, y)| (*x as i32, *y as i32)) {
+            // each side as (outward normal, direction along it), clockwise from the top
+            for ((nx, ny), (dx, dy)) in [
+                ((0, -1), (1, 0)),
+                ((1, 0), (0, 1)),
+                ((0, 1), (-1, 0)),
+                ((-1, 0), (0, -1)),
+            ] {
+                if same(x + nx, y + ny) {
+                    continue;
+                }
+                // how far each end reaches past the cell's inset corner: into the next cell
+                // when the side goes on there, around the neighbouring cage cell at an inner
+                // corner
+                let reach = |sign: i32| {
+                    let (ax, ay) = (x + sign * dx, y + sign * dy);
+                    if !same(ax, ay) {
+                        0.
+                    } else if same(ax + nx, ay + ny) {
+                        2. * inset
+                    } else {
+                        inset
+                    }
+                };
+                let center = (
+                    origin.0 + (x as f32 + 0.5) * cell,
+                    origin.1 + (y as f32 + 0.5) * cell,
+                );
+                let half = cell / 2. - inset;
+                let side = (center.0 + nx as f32 * half, center.1 + ny as f32 * half);
+                let end = |sign: i32, reach: f32| {
+                    let length = half + reach;
+                    (
+                        side.0 + (sign * dx) as f32 * length,
+                        side.1 + (sign * dy) as f32 * length,
+                    )
+                };
+                shapes.push(Shape::Line {
+                    from: end(-1, reach(-1)),
+                    to: end(1, reach(1)),
+                    width: cell / 40.,
+                    color: CAGE_COLOR,
+                    dashed: true,
+                });
+            }
+        }
+
+        let (x, y) = cells[0];
+        let clue = match board.get_sum(x, y) {
+            0 => continue,
+            sum if board.has_operators() => format!("{}{}", sum, board.get_operator(x, y).label()),
+            sum => sum.to_string(),
+        };
+        let (left, top) = (
+            origin.0 + x as f32 * cell + inset / 2.,
+            origin.1 + y as f32 * cell + inset / 2.,
+        );
+        let text_size = cell * 0.24;
+        // the clue covers the corner of the outline
+        shapes.push(Shape::Rect {
+            x: left,
+            y: top,
+            width: text_size * 0.6 * clue.chars().count() as f32 + inset,
+            height: text_size,
+            color: WHITE,
+        });
+        shapes.push(Shape::Text {
+            x: left + inset / 4.,
+            y: top + text_size * 0.85,
+            size: text_size,
+            text: clue,
+            color: BLACK,
+            anchor: Anchor::Start,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::board::Board;
+    use crate::render::{render, render_pages, RenderOptions, Shape, A4};
+
+    #[test]
+    fn check_render_pages() {
+        let board = Board::sample_puzzle();
+        let scene = render("", &board, &RenderOptions::new());
+        assert_eq!((scene.width, scene.height), (400., 400.));
+        let texts = |scene: &crate::render::Scene| {
+            scene
+                .shapes
+                .iter()
+                .filter(|s| matches!(s, Shape::Text { .. }))
+                .count()
+        };
+        // one clue per cage and no digits
+        assert_eq!(texts(&scene), 29);
+
+        let puzzles = vec![("sample".to_string(), board); 5];
+        let pages = render_pages(&puzzles, &RenderOptions::new().with_per_page(4));
+        assert_eq!(pages.len(), 2);
+        assert_eq!((pages[0].width, pages[0].height), A4);
+        assert_eq!(texts(&pages[0]), 4 * 30);
+        assert_eq!(texts(&pages[1]), 30);
+        for shape in &pages[0].shapes {
+            if let Shape::Line { from, to, .. } = shape {
+                assert!(from.0 == to.0 || from.1 == to.1);
+                assert!([from.0, to.0].iter().all(|x| *x >= 0. && *x <= A4.0));
+            }
+        }
+    }
+}
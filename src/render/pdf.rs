@@ -0,0 +1,213 @@
+use crate::render::{Anchor, Rgb, Scene, Shape};
+use std::fmt::Write;
+
+/// PDF points per scene pixel, 72 to the inch against 96
+const POINTS: f32 = 0.75;
+
+/// widths of the digits and the space in Helvetica, per 1000 units of font size. Other
+/// characters are taken as wide as a digit, only digits are centered
+const DIGIT_WIDTH: f32 = 556.;
+const SPACE_WIDTH: f32 = 278.;
+
+/// the scenes as the pages of one PDF document, text is set in the built-in Helvetica so no
+/// font has to be embedded
+pub fn to_pdf(title: &str, pages: &[Scene]) -> Vec<u8> {
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            (0..pages.len())
+                .map(|i| format!("{} 0 R", 5 + 2 * i))
+                .collect::<Vec<_>>()
+                .join(" "),
+            pages.len()
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+        format!("<< /Title ({}) /Producer (killer-sudoku) >>", escape(title)),
+    ];
+    for (i, page) in pages.iter().enumerate() {
+        let content = content(page);
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+             /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            number(page.width * POINTS),
+            number(page.height * POINTS),
+            6 + 2 * i
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ));
+    }
+
+    let mut out = b"%PDF-1.4\n".to_vec();
+    let mut offsets = vec![];
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend(format!("{} 0 obj\n", i + 1).bytes());
+        out.extend(object.bytes());
+        out.extend(b"\nendobj\n");
+    }
+    let xref = out.len();
+    let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        writeln!(table, "{:010} 00000 n ", offset).unwrap();
+    }
+    write!(
+        table,
+        "trailer\n<< /Size {} /Root 1 0 R /Info 4 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    )
+    .unwrap();
+    out.extend(table.bytes());
+    out
+}
+
+/// the drawing operators of a page, the y axis of PDF points up
+fn content(scene: &Scene) -> String {
+    let point = |(x, y): (f32, f32)| {
+        format!(
+            "{} {}",
+            number(x * POINTS),
+            number((scene.height - y) * POINTS)
+        )
+    };
+    let mut out = String::new();
+    for shape in &scene.shapes {
+        match shape {
+            Shape::Rect {
+                x,
+                y,
+                width,
+                height,
+                color,
+            } => writeln!(
+                out,
+                "{} rg {} {} {} re f",
+                rgb(*color),
+                point((*x, y + height)),
+                number(width * POINTS),
+                number(height * POINTS)
+            ),
+            Shape::Line {
+                from,
+                to,
+                width,
+                color,
+                dashed,
+            } => {
+                let width = width * POINTS;
+                let dash = match dashed {
+                    true => format!("[{} {}]", number(width * 4.), number(width * 3.)),
+                    false => "[]".to_string(),
+                };
+                writeln!(
+                    out,
+                    "{} RG {} w 2 J {} 0 d {} m {} l S",
+                    rgb(*color),
+                    number(width),
+                    dash,
+                    point(*from),
+                    point(*to)
+                )
+            }
+            Shape::Text {
+                x,
+                y,
+                size,
+                text,
+                color,
+                anchor,
+            } => {
+                let x = match anchor {
+                    Anchor::Start => *x,
+                    Anchor::Middle => x - text_width(text, *size) / 2.,
+                };
+                writeln!(
+                    out,
+                    "BT /F1 {} Tf {} rg {} Td ({}) Tj ET",
+                    number(size * POINTS),
+                    rgb(*color),
+                    point((x, *y)),
+                    escape(text)
+                )
+            }
+        }
+        .unwrap();
+    }
+    out
+}
+
+fn text_width(text: &str, size: f32) -> f32 {
+    text.chars()
+        .map(|c| match c {
+            ' ' => SPACE_WIDTH,
+            _ => DIGIT_WIDTH,
+        })
+        .sum::<f32>()
+        * size
+        / 1000.
+}
+
+fn number(value: f32) -> String {
+    let text = format!("{:.2}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn rgb(color: Rgb) -> String {
+    color
+        .iter()
+        .map(|c| number(*c as f32 / 255.))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// a string literal in WinAnsi, which matches Latin-1 for the characters used here. Others
+/// become `?`
+fn escape(text: &str) -> String {
+    let mut out = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' | '(' | ')' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            '\u{a0}'..='\u{ff}' => write!(out, "\\{:03o}", c as u32).unwrap(),
+            _ => out.push('?'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::board::Board;
+    use crate::render::pdf::to_pdf;
+    use crate::render::{render_pages, RenderOptions};
+
+    #[test]
+    fn check_pdf() {
+        let puzzles = vec![("(sample)".to_string(), Board::sample_puzzle()); 3];
+        let pages = render_pages(&puzzles, &RenderOptions::new().with_per_page(2));
+        let pdf = to_pdf("sample", &pages);
+        let text = String::from_utf8(pdf.clone()).unwrap();
+        assert!(text.starts_with("%PDF-1.4\n"));
+        assert!(text.ends_with("%%EOF\n"));
+        assert_eq!(text.matches("/Type /Page ").count(), 2);
+        assert!(text.contains("/MediaBox [0 0 595.28 841.88]"));
+        assert!(text.contains("(\\(sample\\)) Tj"));
+
+        // the cross-reference table points at every object
+        let start = text.rsplit("startxref\n").next().unwrap();
+        let xref = start.lines().next().unwrap().parse::<usize>().unwrap();
+        assert!(text[xref..].starts_with("xref\n0 9\n"));
+        for (i, line) in text[xref..].lines().skip(3).take(8).enumerate() {
+            let offset = line[..10].parse::<usize>().unwrap();
+            assert!(text[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use crate::render::{Anchor, Rgb, Scene, Shape, WHITE};
+use ab_glyph::{point, Font, FontRef, ScaleFont};
+
+/// the font the app uses, for the digits and clues
+pub const FONT_PATH: &str = "./assets/font.ttf";
+
+/// an RGB image, row by row
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    fn blend(&mut self, x: i64, y: i64, color: Rgb, coverage: f32) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return;
+        }
+        let i = (y as usize * self.width as usize + x as usize) * 3;
+        for (channel, value) in self.pixels[i..i + 3].iter_mut().zip(color) {
+            *channel = (*channel as f32 * (1. - coverage) + value as f32 * coverage).round() as u8;
+        }
+    }
+    /// fills the pixels whose centers lie inside the rectangle, keeping at least one pixel so
+    /// thin lines don't vanish
+    fn fill(&mut self, (left, top): (f32, f32), (right, bottom): (f32, f32), color: Rgb) {
+        let (x0, y0) = (left.round() as i64, top.round() as i64);
+        let (x1, y1) = (
+            (right.round() as i64).max(x0 + 1),
+            (bottom.round() as i64).max(y0 + 1),
+        );
+        for y in y0..y1 {
+            for x in x0..x1 {
+                self.blend(x, y, color, 1.);
+            }
+        }
+    }
+    pub fn to_png(&self) -> Result<Vec<u8>, String> {
+        let mut out = vec![];
+        let mut encoder = png::Encoder::new(&mut out, self.width, self.height);
+        encoder.set_color(png::ColorType::RGB);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        writer
+            .write_image_data(&self.pixels)
+            .map_err(|e| e.to_string())?;
+        drop(writer);
+        Ok(out)
+    }
+}
+
+/// draws the scene at `scale` pixels per scene unit
+pub fn rasterize(scene: &Scene, scale: f32, font: &impl Font) -> Image {
+    let (width, height) = (
+        (scene.width * scale).ceil() as u32,
+        (scene.height * scale).ceil() as u32,
+    );
+    let mut image = Image {
+        width,
+        height,
+        pixels: WHITE.repeat((width * height) as usize),
+    };
+    for shape in &scene.shapes {
+        match shape {
+            Shape::Rect {
+                x,
+                y,
+                width,
+                height,
+                color,
+            } => image.fill(
+                (x * scale, y * scale),
+                ((x + width) * scale, (y + height) * scale),
+                *color,
+            ),
+            Shape::Line {
+                from,
+                to,
+                width,
+                color,
+                dashed,
+            } => {
+                let half = width * scale / 2.;
+                let (from, to) = (
+                    (from.0 * scale, from.1 * scale),
+                    (to.0 * scale, to.1 * scale),
+                );
+                let length = (to.0 - from.0).abs() + (to.1 - from.1).abs();
+                let direction = ((to.0 - from.0) / length, (to.1 - from.1) / length);
+                // the same dashes as the SVG
+                let (dash, gap) = match dashed {
+                    true => (half * 8., half * 6.),
+                    false => (length, 0.),
+                };
+                let mut start = 0.;
+                while start < length {
+                    let end = (start + dash).min(length);
+                    let (a, b) = (
+                        (from.0 + direction.0 * start, from.1 + direction.1 * start),
+                        (from.0 + direction.0 * end, from.1 + direction.1 * end),
+                    );
+                    image.fill(
+                        (a.0.min(b.0) - half, a.1.min(b.1) - half),
+                        (a.0.max(b.0) + half, a.1.max(b.1) + half),
+                        *color,
+                    );
+                    start = end + gap;
+                }
+            }
+            Shape::Text {
+                x,
+                y,
+                size,
+                text,
+                color,
+                anchor,
+            } => {
+                let font = font.as_scaled(size * scale);
+                let ids = text.chars().map(|c| font.glyph_id(c)).collect::<Vec<_>>();
+                let advance = |i: usize| {
+                    let kern = ids.get(i + 1).map_or(0., |next| font.kern(ids[i], *next));
+                    font.h_advance(ids[i]) + kern
+                };
+                let width = (0..ids.len()).map(advance).sum::<f32>();
+                let mut pen = match anchor {
+                    Anchor::Start => x * scale,
+                    Anchor::Middle => x * scale - width / 2.,
+                };
+                for (i, id) in ids.iter().enumerate() {
+                    let glyph = id.with_scale_and_position(size * scale, point(pen, y * scale));
+                    if let Some(outline) = font.outline_glyph(glyph) {
+                        let bounds = outline.px_bounds();
+                        outline.draw(|gx, gy, coverage| {
+                            image.blend(
+                                bounds.min.x as i64 + gx as i64,
+                                bounds.min.y as i64 + gy as i64,
+                                *color,
+                                coverage.min(1.),
+                            )
+                        });
+                    }
+                    pen += advance(i);
+                }
+            }
+        }
+    }
+    image
+}
+
+/// the scene as a PNG file, in the app's font
+pub fn to_png(scene: &Scene, scale: f32) -> Result<Vec<u8>, String> {
+    let data =
+        std::fs::read(FONT_PATH).map_err(|e| format!("could not read {}: {}", FONT_PATH, e))?;
+    let font = FontRef::try_from_slice(&data).map_err(|e| format!("{}: {}", FONT_PATH, e))?;
+    rasterize(scene, scale, &font).to_png()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::board::Board;
+    use crate::render::raster::{rasterize, to_png, FONT_PATH};
+    use crate::render::{render, RenderOptions};
+    use ab_glyph::FontRef;
+
+    #[test]
+    fn check_rasterize() {
+        let board = Board::sample_puzzle();
+        let scene = render("", &board, &RenderOptions::new().with_cell(20.));
+        let data = std::fs::read(FONT_PATH).unwrap();
+        let image = rasterize(&scene, 2., &FontRef::try_from_slice(&data).unwrap());
+        assert_eq!((image.width, image.height), (400, 400));
+        let pixel = |x: usize, y: usize| &image.pixels[(y * 400 + x) * 3..][..3];
+        // the board's corner is on its border, the middle of a cell without a clue is blank
+        assert_eq!(pixel(20, 20), [0, 0, 0]);
+        assert_eq!(pixel(20 + 40 * 4 + 20, 20 + 40 * 4 + 20), [255, 255, 255]);
+
+        let png = to_png(&scene, 1.).unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+        // the width and height in the header
+        assert_eq!(&png[16..24], &[0, 0, 0, 200, 0, 0, 0, 200]);
+    }
+}
@@ -0,0 +1,113 @@
+use crate::render::{Anchor, Rgb, Scene, Shape};
+use std::fmt::Write;
+
+/// the scene as an SVG document, text is set in the viewer's sans-serif font
+pub fn to_svg(scene: &Scene) -> String {
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+         viewBox=\"0 0 {w} {h}\">\n<rect width=\"{w}\" height=\"{h}\" fill=\"white\"/>\n",
+        w = scene.width,
+        h = scene.height
+    );
+    for shape in &scene.shapes {
+        match shape {
+            Shape::Rect {
+                x,
+                y,
+                width,
+                height,
+                color,
+            } => writeln!(
+                out,
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+                x,
+                y,
+                width,
+                height,
+                hex(*color)
+            ),
+            Shape::Line {
+                from,
+                to,
+                width,
+                color,
+                dashed,
+            } => {
+                let dash = if *dashed {
+                    format!(" stroke-dasharray=\"{} {}\"", width * 4., width * 3.)
+                } else {
+                    String::new()
+                };
+                writeln!(
+                    out,
+                    "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" \
+                     stroke-width=\"{}\" stroke-linecap=\"square\"{}/>",
+                    from.0,
+                    from.1,
+                    to.0,
+                    to.1,
+                    hex(*color),
+                    width,
+                    dash
+                )
+            }
+            Shape::Text {
+                x,
+                y,
+                size,
+                text,
+                color,
+                anchor,
+            } => {
+                let anchor = match anchor {
+                    Anchor::Start => "start",
+                    Anchor::Middle => "middle",
+                };
+                writeln!(
+                    out,
+                    "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"{}\" \
+                     fill=\"{}\" text-anchor=\"{}\">{}</text>",
+                    x,
+                    y,
+                    size,
+                    hex(*color),
+                    anchor,
+                    escape(text)
+                )
+            }
+        }
+        .unwrap();
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+fn hex([r, g, b]: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::board::Board;
+    use crate::render::svg::to_svg;
+    use crate::render::{render, Overlay, RenderOptions};
+
+    #[test]
+    fn check_svg() {
+        let board = Board::sample_puzzle();
+        let options = RenderOptions::new().with_overlay(Overlay::Solution);
+        let svg = to_svg(&render("<sample>", &board, &options));
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>\n"));
+        assert!(svg.contains("&lt;sample&gt;"));
+        // the title, 29 clues and 81 digits of the solution
+        assert_eq!(svg.matches("<text").count(), 1 + 29 + 81);
+        assert!(svg.contains("stroke-dasharray"));
+    }
+}
@@ -0,0 +1,401 @@
+use crate::data::board::Board;
+use crate::data::cage_analysis::{derived_cages, DerivedCage};
+use crate::data::node::Node;
+use crate::solvers::budget::{Budget, SolveOutcome};
+use crate::solvers::search_tree::{Outcome, PruneReason, SearchTree, Step};
+use crate::solvers::stats::{search_bytes, SolveStats};
+use std::collections::HashMap;
+
+/// Depth-first backtracking search. Which empty cell it branches on and in which order it
+/// tries the digits are picked with `CellOrder` and `ValueOrder`, by default the cell with the
+/// fewest possible digits, trying those in ascending order.
+#[derive(Debug, Default, Clone)]
+pub struct Backtracking {
+    cell_order: CellOrder,
+    value_order: ValueOrder,
+}
+
+/// Which empty cell to branch on next.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum CellOrder {
+    /// the first empty cell, row by row
+    First,
+    /// the cell with the fewest possible digits (minimum remaining values)
+    #[default]
+    MinimumRemaining,
+    /// minimum remaining values, ties go to the cell with the most empty peers (degree)
+    Degree,
+    /// a cell of the smallest cage, ties go to the fewest possible digits
+    CageSizeFirst,
+    /// a cell of the cage with the fewest empty cells left, ties go to the fewest possible
+    /// digits
+    MostConstrainedCage,
+}
+
+/// In which order to try the digits of a cell.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum ValueOrder {
+    #[default]
+    Ascending,
+    /// the digit that is still possible in the fewest empty peers first
+    LeastConstraining,
+}
+
+impl CellOrder {
+    pub const ALL: [CellOrder; 5] = [
+        CellOrder::First,
+        CellOrder::MinimumRemaining,
+        CellOrder::Degree,
+        CellOrder::CageSizeFirst,
+        CellOrder::MostConstrainedCage,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CellOrder::First => "first",
+            CellOrder::MinimumRemaining => "mrv",
+            CellOrder::Degree => "degree",
+            CellOrder::CageSizeFirst => "cage-size",
+            CellOrder::MostConstrainedCage => "constrained-cage",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|o| o.name() == name).copied()
+    }
+}
+
+impl ValueOrder {
+    pub const ALL: [ValueOrder; 2] = [ValueOrder::Ascending, ValueOrder::LeastConstraining];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ValueOrder::Ascending => "ascending",
+            ValueOrder::LeastConstraining => "lcv",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|o| o.name() == name).copied()
+    }
+}
+
+impl Backtracking {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_order(cell_order: CellOrder, value_order: ValueOrder) -> Self {
+        Self {
+            cell_order,
+            value_order,
+        }
+    }
+    pub fn get_cell_order(&self) -> CellOrder {
+        self.cell_order
+    }
+    pub fn get_value_order(&self) -> ValueOrder {
+        self.value_order
+    }
+
+    /// the name of the solver with its orderings, as shown in `SolveStats`
+    pub fn name(&self) -> String {
+        format!(
+            "backtracking ({} + {})",
+            self.cell_order.name(),
+            self.value_order.name()
+        )
+    }
+
+    /// the first solution in search order, `None` if the puzzle has no solution
+    pub fn solve(&self, board: &Board) -> Option<Board> {
+        self.solve_with_stats(board).0
+    }
+
+    /// like `solve`, also returning what the search did
+    pub fn solve_with_stats(&self, board: &Board) -> (Option<Board>, SolveStats) {
+        let (outcome, stats) = self.solve_within(board, &Budget::new());
+        (outcome.solution(), stats)
+    }
+
+    /// like `solve_with_stats`, giving up once `budget` runs out. the stats then cover the
+    /// part of the search that was done
+    pub fn solve_within(&self, board: &Board, budget: &Budget) -> (SolveOutcome, SolveStats) {
+        self.run(board, budget, None)
+    }
+
+    /// like `solve`, also recording every node the search visits
+    pub fn solve_recorded(&self, board: &Board) -> (Option<Board>, SearchTree) {
+        let (outcome, _, tree) = self.solve_recorded_within(board, &Budget::new());
+        (outcome.solution(), tree)
+    }
+
+    /// like `solve_within`, also recording every node the search visits. the budget keeps the
+    /// tree from growing without bound, nodes left unfinished are `Cancelled`
+    pub fn solve_recorded_within(
+        &self,
+        board: &Board,
+        budget: &Budget,
+    ) -> (SolveOutcome, SolveStats, SearchTree) {
+        let mut tree = SearchTree::new(board.clone());
+        let (outcome, stats) = self.run(board, budget, Some(&mut tree.root));
+        (outcome, stats, tree)
+    }
+
+    fn run(
+        &self,
+        board: &Board,
+        budget: &Budget,
+        node: Option<&mut Node<Step>>,
+    ) -> (SolveOutcome, SolveStats) {
+        let mut stats = SolveStats::new(&self.name());
+        let check = budget.start();
+        let mut solution = None;
+        let derived = stats.time("derive cages", |_| derived_cages(board));
+        stats.time("search", |stats| {
+            self.search(
+                &mut board.clone(),
+                0,
+                &derived,
+                &mut |solved| {
+                    solution = Some(solved.clone());
+                    false
+                },
+                &|| check.exceeded(),
+                node,
+                stats,
+            )
+        });
+        stats.estimated_memory = search_bytes(board, stats.max_depth);
+        stats.gave_up = check.hit();
+        (check.outcome(solution), stats)
+    }
+
+    /// counts solutions, stopping once `limit` are found (2 is enough to check uniqueness)
+    pub fn count_solutions(&self, board: &Board, limit: usize) -> usize {
+        if limit == 0 {
+            return 0;
+        }
+        let mut count = 0;
+        self.search(
+            &mut board.clone(),
+            0,
+            &derived_cages(board),
+            &mut |_| {
+                count += 1;
+                count < limit
+            },
+            &|| false,
+            None,
+            &mut SolveStats::default(),
+        );
+        count
+    }
+
+    /// up to `limit` solutions, in the order the search finds them
+    pub fn solutions(&self, board: &Board, limit: usize) -> Vec<Board> {
+        let mut solutions = vec![];
+        if limit == 0 {
+            return solutions;
+        }
+        self.search(
+            &mut board.clone(),
+            0,
+            &derived_cages(board),
+            &mut |solved| {
+                solutions.push(solved.clone());
+                solutions.len() < limit
+            },
+            &|| false,
+            None,
+            &mut SolveStats::default(),
+        );
+        solutions
+    }
+
+    /// counts the nodes visited until the first solution, to compare orderings
+    pub fn count_nodes(&self, board: &Board) -> usize {
+        self.solve_with_stats(board).1.nodes
+    }
+
+    /// the empty cell to branch on next and the digits it can take in the order to try them,
+    /// `None` when the board is full. digits that leave one of the `derived` cages unable to
+    /// reach its sum are left out
+    pub fn next_cell(
+        &self,
+        board: &Board,
+        derived: &[DerivedCage],
+    ) -> Option<((usize, usize), Vec<i32>)> {
+        let size = board.get_size();
+        // (size, empty cells) of every cage, only needed by the cage orders
+        let mut cages = HashMap::new();
+        if matches!(
+            self.cell_order,
+            CellOrder::CageSizeFirst | CellOrder::MostConstrainedCage
+        ) {
+            for y in 0..size {
+                for x in 0..size {
+                    let cage = cages.entry(board.get_cage_index(x, y)).or_insert((0, 0));
+                    cage.0 += 1;
+                    if board.get_value(x, y) == 0 {
+                        cage.1 += 1;
+                    }
+                }
+            }
+        }
+
+        let mut best: Option<((usize, usize), Vec<i32>)> = None;
+        let mut best_key = (usize::MAX, usize::MAX);
+        for y in 0..size {
+            for x in 0..size {
+                if board.get_value(x, y) != 0 {
+                    continue;
+                }
+                let around = derived
+                    .iter()
+                    .filter(|d| d.cells.contains(&(x, y)))
+                    .collect::<Vec<_>>();
+                let candidates = (1..=size as i32)
+                    .filter(|num| {
+                        board.is_possible(x, y, *num)
+                            && around.iter().all(|d| d.allows(board, x, y, *num))
+                    })
+                    .collect::<Vec<_>>();
+                if candidates.is_empty() || self.cell_order == CellOrder::First {
+                    return Some(((x, y), self.order_values(board, x, y, candidates)));
+                }
+                let key = match self.cell_order {
+                    CellOrder::First | CellOrder::MinimumRemaining => (candidates.len(), 0),
+                    CellOrder::Degree => {
+                        let empty_peers = board
+                            .get_peers(x, y)
+                            .iter()
+                            .filter(|(px, py)| board.get_value(*px, *py) == 0)
+                            .count();
+                        (candidates.len(), usize::MAX - empty_peers)
+                    }
+                    CellOrder::CageSizeFirst => {
+                        (cages[&board.get_cage_index(x, y)].0, candidates.len())
+                    }
+                    CellOrder::MostConstrainedCage => {
+                        (cages[&board.get_cage_index(x, y)].1, candidates.len())
+                    }
+                };
+                if key < best_key {
+                    best = Some(((x, y), candidates));
+                    best_key = key;
+                }
+            }
+        }
+        best.map(|((x, y), candidates)| ((x, y), self.order_values(board, x, y, candidates)))
+    }
+
+    fn order_values(
+        &self,
+        board: &Board,
+        x: usize,
+        y: usize,
+        mut candidates: Vec<i32>,
+    ) -> Vec<i32> {
+        if self.value_order == ValueOrder::LeastConstraining {
+            let empty_peers = board
+                .get_peers(x, y)
+                .into_iter()
+                .filter(|(px, py)| board.get_value(*px, *py) == 0)
+                .collect::<Vec<_>>();
+            // placing `num` takes it away from every peer that could still hold it
+            candidates.sort_by_cached_key(|num| {
+                empty_peers
+                    .iter()
+                    .filter(|(px, py)| board.is_possible(*px, *py, *num))
+                    .count()
+            });
+        }
+        candidates
+    }
+
+    /// calls `visit` for every solution below `board` until it returns false or `stop` does.
+    /// returns false if the search was cut short, the board is restored either way.
+    /// when given a `node`, the visited part of the tree is recorded below it. `depth` is the
+    /// number of digits placed since the search started, for `stats`. `derived` are the derived
+    /// cages of the board, they only depend on its cages so they are found once up front
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn search(
+        &self,
+        board: &mut Board,
+        depth: usize,
+        derived: &[DerivedCage],
+        visit: &mut dyn FnMut(&Board) -> bool,
+        stop: &dyn Fn() -> bool,
+        mut node: Option<&mut Node<Step>>,
+        stats: &mut SolveStats,
+    ) -> bool {
+        if stop() {
+            return false;
+        }
+        stats.nodes += 1;
+        stats.max_depth = stats.max_depth.max(depth);
+        let ((x, y), candidates) = match self.next_cell(board, derived) {
+            Some(next) => next,
+            None if board.is_finished() => {
+                record(node, Outcome::Solution);
+                stats.solutions += 1;
+                return visit(board);
+            }
+            None => {
+                record(node, Outcome::Pruned(PruneReason::BrokenRule));
+                return true;
+            }
+        };
+        if candidates.is_empty() {
+            record(node, Outcome::Pruned(PruneReason::NoCandidates { x, y }));
+            return true;
+        }
+        record(node.as_deref_mut(), Outcome::Branched { x, y });
+        if candidates.len() == 1 {
+            stats.forced += 1;
+        }
+
+        for num in candidates {
+            board.unchecked_set_num(x, y, num);
+            let keep_going = match node.as_deref_mut() {
+                Some(node) => {
+                    let step = node.get_data().as_ref().unwrap().child(x, y, num);
+                    let child = node.add_child(Node::new(step));
+                    self.search(board, depth + 1, derived, visit, stop, Some(child), stats)
+                }
+                None => self.search(board, depth + 1, derived, visit, stop, None, stats),
+            };
+            board.unchecked_set_num(x, y, 0);
+            if !keep_going {
+                return false;
+            }
+            stats.backtracks += 1;
+        }
+        true
+    }
+}
+
+fn record(node: Option<&mut Node<Step>>, outcome: Outcome) {
+    if let Some(Some(step)) = node.map(|n| n.get_data_mut()) {
+        step.outcome = outcome;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::board::Board;
+    use crate::solvers::backtracking::{Backtracking, CellOrder, ValueOrder};
+
+    #[test]
+    fn check_orderings() {
+        let board = Board::sample_with_rows(2);
+        let solution = Backtracking::new().solve(&board).unwrap();
+        for cell_order in CellOrder::ALL {
+            for value_order in ValueOrder::ALL {
+                let solver = Backtracking::with_order(cell_order, value_order);
+                assert_eq!(solver.solve(&board).as_ref(), Some(&solution));
+                assert_eq!(CellOrder::from_name(cell_order.name()), Some(cell_order));
+            }
+        }
+        let first = Backtracking::with_order(CellOrder::First, ValueOrder::Ascending);
+        assert!(Backtracking::new().count_nodes(&board) < first.count_nodes(&board));
+    }
+}
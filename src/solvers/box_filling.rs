@@ -0,0 +1,254 @@
+use crate::data::board::Board;
+use crate::data::cage::Operator;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// (x, y)
+pub(crate) type Cell = (usize, usize);
+
+/// A full grid where every box holds each digit once, the candidates of the stochastic solvers.
+pub(crate) type Grid = Vec<Vec<i32>>;
+
+/// What the stochastic solvers need to know about a board to fill and score grids quickly.
+///
+/// The cost of a grid counts repeated digits in rows and columns, how far cages are from their
+/// clue and pairs of cells that break a variant or unique cages. Boxes are always valid.
+#[derive(Debug, Clone)]
+pub(crate) struct BoxRules {
+    pub size: usize,
+    /// the puzzle with only its given digits
+    givens: Grid,
+    /// the cells of every box
+    pub boxes: Vec<Vec<Cell>>,
+    /// the cells of every box that aren't given
+    pub free: Vec<Vec<Cell>>,
+    /// (cells, operator, clue) of every cage with a clue
+    cages: Vec<(Vec<Cell>, Operator, i32)>,
+    /// index into `cages` of every cell
+    cage_of: Vec<Vec<Option<usize>>>,
+    /// cells that may not share a digit because of a variant or unique cages
+    constraint_peers: Vec<Vec<Vec<Cell>>>,
+}
+
+impl BoxRules {
+    pub fn new(board: &Board) -> Self {
+        let size = board.get_size();
+        let (box_width, box_height) = board.get_box_size();
+        let givens = (0..size)
+            .map(|y| (0..size).map(|x| board.get_value(x, y)).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let mut boxes = vec![];
+        for box_y in (0..size).step_by(box_height) {
+            for box_x in (0..size).step_by(box_width) {
+                boxes.push(
+                    (0..box_width * box_height)
+                        .map(|i| (box_x + i % box_width, box_y + i / box_width))
+                        .collect::<Vec<_>>(),
+                );
+            }
+        }
+        let free = boxes
+            .iter()
+            .map(|cells| {
+                cells
+                    .iter()
+                    .filter(|(x, y)| givens[*y][*x] == 0)
+                    .copied()
+                    .collect()
+            })
+            .collect();
+
+        let mut cages = vec![];
+        let mut cage_of = vec![vec![None; size]; size];
+        for y in 0..size {
+            for x in 0..size {
+                let (op, clue) = (board.get_operator(x, y), board.get_sum(x, y));
+                if board.get_cage_index(x, y) == 0 || clue == 0 || op == Operator::Unlabelled {
+                    continue;
+                }
+                if cage_of[y][x].is_none() {
+                    let cells = board.get_cage_cells(x, y);
+                    for (cx, cy) in &cells {
+                        cage_of[*cy][*cx] = Some(cages.len());
+                    }
+                    cages.push((cells, op, clue));
+                }
+            }
+        }
+
+        let constraints = board.get_constraints();
+        let constraint_peers = (0..size)
+            .map(|y| {
+                (0..size)
+                    .map(|x| {
+                        let mut peers = constraints
+                            .iter()
+                            .flat_map(|c| c.peers(board, x, y))
+                            .filter(|p| *p != (x, y))
+                            .collect::<Vec<_>>();
+                        peers.sort_unstable();
+                        peers.dedup();
+                        peers
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            size,
+            givens,
+            boxes,
+            free,
+            cages,
+            cage_of,
+            constraint_peers,
+        }
+    }
+
+    /// whether a box has a given digit twice, no grid can fix that
+    pub fn has_clash(&self) -> bool {
+        self.boxes.iter().any(|cells| {
+            let mut givens = cells
+                .iter()
+                .map(|(x, y)| self.givens[*y][*x])
+                .filter(|n| *n != 0)
+                .collect::<Vec<_>>();
+            let count = givens.len();
+            givens.sort_unstable();
+            givens.dedup();
+            givens.len() != count
+        })
+    }
+
+    /// the givens with the free cells of every box holding its missing digits in random order
+    pub fn random_grid(&self, rng: &mut StdRng) -> Grid {
+        let mut grid = self.givens.clone();
+        for i in 0..self.boxes.len() {
+            self.shuffle_box(&mut grid, i, rng);
+        }
+        grid
+    }
+
+    /// puts the missing digits of box `i` in a new random order
+    pub fn shuffle_box(&self, grid: &mut Grid, i: usize, rng: &mut StdRng) {
+        let mut missing = (1..=self.size as i32)
+            .filter(|n| !self.boxes[i].iter().any(|(x, y)| self.givens[*y][*x] == *n))
+            .collect::<Vec<_>>();
+        missing.shuffle(rng);
+        for ((x, y), n) in self.free[i].iter().zip(missing) {
+            grid[*y][*x] = n;
+        }
+    }
+
+    /// two free cells of one box
+    pub fn random_swap(&self, rng: &mut StdRng) -> Option<(Cell, Cell)> {
+        let boxes = self
+            .free
+            .iter()
+            .filter(|b| b.len() >= 2)
+            .collect::<Vec<_>>();
+        let cells = boxes.choose(rng)?;
+        let a = rng.gen_range(0..cells.len());
+        let mut b = rng.gen_range(0..cells.len() - 1);
+        if b >= a {
+            b += 1;
+        }
+        Some((cells[a], cells[b]))
+    }
+
+    fn line_cost(&self, grid: &Grid, cells: impl Iterator<Item = Cell>) -> usize {
+        let mut seen = vec![false; self.size + 1];
+        let mut repeats = 0;
+        for (x, y) in cells {
+            let n = grid[y][x] as usize;
+            if seen[n] {
+                repeats += 1;
+            }
+            seen[n] = true;
+        }
+        repeats
+    }
+    pub fn row_cost(&self, grid: &Grid, y: usize) -> usize {
+        self.line_cost(grid, (0..self.size).map(|x| (x, y)))
+    }
+    pub fn column_cost(&self, grid: &Grid, x: usize) -> usize {
+        self.line_cost(grid, (0..self.size).map(|y| (x, y)))
+    }
+    pub fn cage_cost(&self, grid: &Grid, index: usize) -> usize {
+        let (cells, op, clue) = &self.cages[index];
+        let digits = cells.iter().map(|(x, y)| grid[*y][*x]).collect::<Vec<_>>();
+        match (op, op.evaluate(&digits)) {
+            (Operator::Sum, Some(sum)) => (sum - clue).unsigned_abs() as usize,
+            (_, result) => (result != Some(*clue)) as usize,
+        }
+    }
+    fn conflicts(&self, grid: &Grid, x: usize, y: usize) -> usize {
+        self.constraint_peers[y][x]
+            .iter()
+            .filter(|(px, py)| grid[*py][*px] == grid[y][x])
+            .count()
+    }
+
+    /// 0 exactly when `grid` solves the puzzle
+    pub fn cost(&self, grid: &Grid) -> usize {
+        let lines = (0..self.size)
+            .map(|i| self.row_cost(grid, i) + self.column_cost(grid, i))
+            .sum::<usize>();
+        let cages = (0..self.cages.len())
+            .map(|i| self.cage_cost(grid, i))
+            .sum::<usize>();
+        // every clashing pair is seen from both cells
+        let conflicts = (0..self.size * self.size)
+            .map(|i| self.conflicts(grid, i % self.size, i / self.size))
+            .sum::<usize>()
+            / 2;
+        lines + cages + conflicts
+    }
+
+    /// the part of the cost that swapping `a` and `b` can change
+    fn local_cost(&self, grid: &Grid, a: Cell, b: Cell) -> usize {
+        let mut cost = self.row_cost(grid, a.1) + self.column_cost(grid, a.0);
+        if b.1 != a.1 {
+            cost += self.row_cost(grid, b.1);
+        }
+        if b.0 != a.0 {
+            cost += self.column_cost(grid, b.0);
+        }
+        let (cage_a, cage_b) = (self.cage_of[a.1][a.0], self.cage_of[b.1][b.0]);
+        if let Some(i) = cage_a {
+            cost += self.cage_cost(grid, i);
+        }
+        if let Some(i) = cage_b.filter(|i| cage_a != Some(*i)) {
+            cost += self.cage_cost(grid, i);
+        }
+        // a and b hold different digits, so they never clash with each other
+        cost + self.conflicts(grid, a.0, a.1) + self.conflicts(grid, b.0, b.1)
+    }
+
+    /// how much the cost changes when `a` and `b` are swapped, `grid` ends up unchanged
+    pub fn swap_delta(&self, grid: &mut Grid, a: Cell, b: Cell) -> i64 {
+        let before = self.local_cost(grid, a, b) as i64;
+        swap(grid, a, b);
+        let after = self.local_cost(grid, a, b) as i64;
+        swap(grid, a, b);
+        after - before
+    }
+
+    pub fn to_board(&self, board: &Board, grid: &Grid) -> Board {
+        let mut solved = board.clone();
+        for (y, row) in grid.iter().enumerate() {
+            for (x, num) in row.iter().enumerate() {
+                solved.unchecked_set_num(x, y, *num);
+            }
+        }
+        solved
+    }
+}
+
+pub(crate) fn swap(grid: &mut Grid, a: Cell, b: Cell) {
+    let tmp = grid[a.1][a.0];
+    grid[a.1][a.0] = grid[b.1][b.0];
+    grid[b.1][b.0] = tmp;
+}
@@ -0,0 +1,180 @@
+use crate::data::board::Board;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How much a solver may spend before giving up. The default has no limits.
+#[derive(Debug, Default, Clone)]
+pub struct Budget {
+    time: Option<Duration>,
+    nodes: Option<usize>,
+    token: Option<CancelToken>,
+}
+
+/// Stops a search from another thread, the search notices at its next node.
+#[derive(Debug, Default, Clone)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+/// The budget limit a search ran into.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+pub enum Limit {
+    Time,
+    Nodes,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolveOutcome {
+    Solved(Board),
+    /// the whole search tree was searched without finding a solution
+    Unsolvable,
+    /// the search stopped early, there may still be a solution
+    GaveUp(Limit),
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl Budget {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_time(mut self, time: Duration) -> Self {
+        self.time = Some(time);
+        self
+    }
+    pub fn with_nodes(mut self, nodes: usize) -> Self {
+        self.nodes = Some(nodes);
+        self
+    }
+    pub fn with_token(mut self, token: CancelToken) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// starts the clock, the returned check is shared by every thread of one solve
+    pub fn start(&self) -> BudgetCheck<'_> {
+        BudgetCheck {
+            budget: self,
+            start: Instant::now(),
+            nodes: AtomicUsize::new(0),
+            hit: AtomicU8::new(0),
+        }
+    }
+}
+
+/// A running `Budget`, `exceeded` is called once per search node.
+#[derive(Debug)]
+pub struct BudgetCheck<'a> {
+    budget: &'a Budget,
+    start: Instant,
+    nodes: AtomicUsize,
+    /// the limit that was hit first, 0 for none and `Limit as u8 + 1` otherwise
+    hit: AtomicU8,
+}
+
+impl BudgetCheck<'_> {
+    /// counts a node and tells whether the search should stop
+    pub fn exceeded(&self) -> bool {
+        let nodes = self.nodes.fetch_add(1, Ordering::Relaxed) + 1;
+        let limit = if self.budget.nodes.is_some_and(|max| nodes > max) {
+            Limit::Nodes
+        } else if self.budget.token.as_ref().is_some_and(|t| t.is_cancelled()) {
+            Limit::Cancelled
+        } else if self
+            .budget
+            .time
+            .is_some_and(|max| self.start.elapsed() > max)
+        {
+            Limit::Time
+        } else {
+            return false;
+        };
+        let _ = self
+            .hit
+            .compare_exchange(0, limit as u8 + 1, Ordering::Relaxed, Ordering::Relaxed);
+        true
+    }
+
+    /// the limit the search ran into, if any
+    pub fn hit(&self) -> Option<Limit> {
+        match self.hit.load(Ordering::Relaxed) {
+            0 => None,
+            1 => Some(Limit::Time),
+            2 => Some(Limit::Nodes),
+            _ => Some(Limit::Cancelled),
+        }
+    }
+
+    /// the outcome of a search that found `solution` under this budget
+    pub fn outcome(&self, solution: Option<Board>) -> SolveOutcome {
+        match (solution, self.hit()) {
+            (Some(solution), _) => SolveOutcome::Solved(solution),
+            (None, Some(limit)) => SolveOutcome::GaveUp(limit),
+            (None, None) => SolveOutcome::Unsolvable,
+        }
+    }
+}
+
+impl SolveOutcome {
+    pub fn solution(self) -> Option<Board> {
+        match self {
+            SolveOutcome::Solved(board) => Some(board),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::board::Board;
+    use crate::solvers::backtracking::Backtracking;
+    use crate::solvers::budget::{Budget, CancelToken, Limit, SolveOutcome};
+    use crate::solvers::parallel::Parallel;
+    use std::time::Duration;
+
+    #[test]
+    fn check_budgets() {
+        // an empty grid needs a node for every cell it fills, so one node is never enough
+        // however much pruning the search learns
+        let empty = Board::new(9);
+        let solver = Backtracking::new();
+        let (outcome, stats) = solver.solve_within(&empty, &Budget::new().with_nodes(1));
+        assert_eq!(outcome, SolveOutcome::GaveUp(Limit::Nodes));
+        assert_eq!(stats.gave_up, Some(Limit::Nodes));
+        assert_eq!(stats.nodes, 1);
+
+        let (outcome, _) = Parallel::new(2).solve_within(&empty, &Budget::new().with_nodes(1));
+        assert_eq!(outcome, SolveOutcome::GaveUp(Limit::Nodes));
+
+        let board = Board::sample_puzzle();
+
+        let token = CancelToken::new();
+        token.cancel();
+        let (outcome, _) = solver.solve_within(&board, &Budget::new().with_token(token));
+        assert_eq!(outcome, SolveOutcome::GaveUp(Limit::Cancelled));
+
+        let (outcome, _) = solver.solve_within(&board, &Budget::new().with_time(Duration::ZERO));
+        assert_eq!(outcome, SolveOutcome::GaveUp(Limit::Time));
+
+        // (2, 0) can't hold 1 or 2 from its row nor 3 or 4 from its column
+        let mut stuck = Board::new(4);
+        stuck.unchecked_set_num(0, 0, 1);
+        stuck.unchecked_set_num(1, 0, 2);
+        stuck.unchecked_set_num(2, 1, 3);
+        stuck.unchecked_set_num(2, 2, 4);
+        let (outcome, stats) = solver.solve_within(&stuck, &Budget::new().with_nodes(100));
+        assert_eq!(outcome, SolveOutcome::Unsolvable);
+        assert_eq!(stats.gave_up, None);
+    }
+}
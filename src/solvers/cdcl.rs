@@ -0,0 +1,359 @@
+/// A small conflict-driven clause learning SAT solver.
+///
+/// Two watched literals for propagation, first-UIP clause learning with backjumping, activity
+/// based branching with phase saving and Luby restarts. Learnt clauses are never deleted, which
+/// is fine for the few thousand variables a sudoku needs.
+#[derive(Debug, Clone)]
+pub struct Cdcl {
+    vars: usize,
+    clauses: Vec<Vec<usize>>,
+    /// clauses watching each literal
+    watches: Vec<Vec<usize>>,
+    assigns: Vec<Option<bool>>,
+    level: Vec<usize>,
+    /// the clause that implied each variable, `None` for decisions
+    reason: Vec<Option<usize>>,
+    trail: Vec<usize>,
+    /// where each decision level starts on the trail
+    trail_lim: Vec<usize>,
+    queue_head: usize,
+    activity: Vec<f64>,
+    activity_inc: f64,
+    /// the last value of each variable, tried first when branching on it again
+    phase: Vec<bool>,
+    seen: Vec<bool>,
+    /// an empty clause or two contradicting units were added
+    unsat: bool,
+    pub decisions: usize,
+    pub conflicts: usize,
+    pub propagations: usize,
+    /// the deepest decision level reached
+    pub max_level: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SatResult {
+    /// the value of every variable, `model[v - 1]` for DIMACS variable `v`
+    Sat(Vec<bool>),
+    Unsat,
+    /// `stop` ended the search
+    Unknown,
+}
+
+/// conflicts before the first restart, later restarts are multiples of the Luby sequence
+const RESTART_BASE: usize = 100;
+const ACTIVITY_DECAY: f64 = 0.95;
+
+/// literals are `2 * var + negated` with 0-based variables
+fn literal(dimacs: i32) -> usize {
+    2 * (dimacs.unsigned_abs() as usize - 1) + (dimacs < 0) as usize
+}
+
+fn value(assigns: &[Option<bool>], lit: usize) -> Option<bool> {
+    assigns[lit >> 1].map(|v| v != (lit & 1 == 1))
+}
+
+/// 1, 1, 2, 1, 1, 2, 4, 1, 1, 2, ...
+fn luby(mut i: usize) -> usize {
+    let mut size = 1;
+    let mut seq = 0;
+    while size < i + 1 {
+        seq += 1;
+        size = 2 * size + 1;
+    }
+    while size - 1 != i {
+        size = (size - 1) >> 1;
+        seq -= 1;
+        i %= size;
+    }
+    1 << seq
+}
+
+impl Cdcl {
+    /// a solver for `vars` variables and `clauses` of DIMACS literals
+    pub fn new(vars: usize, clauses: &[Vec<i32>]) -> Self {
+        let mut solver = Self {
+            vars,
+            clauses: vec![],
+            watches: vec![vec![]; 2 * vars],
+            assigns: vec![None; vars],
+            level: vec![0; vars],
+            reason: vec![None; vars],
+            trail: vec![],
+            trail_lim: vec![],
+            queue_head: 0,
+            activity: vec![0.; vars],
+            activity_inc: 1.,
+            phase: vec![false; vars],
+            seen: vec![false; vars],
+            unsat: false,
+            decisions: 0,
+            conflicts: 0,
+            propagations: 0,
+            max_level: 0,
+        };
+        for clause in clauses {
+            solver.add_clause(clause);
+        }
+        solver
+    }
+
+    fn add_clause(&mut self, clause: &[i32]) {
+        let mut lits = clause.iter().map(|l| literal(*l)).collect::<Vec<_>>();
+        lits.sort_unstable();
+        lits.dedup();
+        if lits.windows(2).any(|w| w[0] ^ 1 == w[1]) {
+            return; // always true
+        }
+        match lits.len() {
+            0 => self.unsat = true,
+            1 => match value(&self.assigns, lits[0]) {
+                Some(true) => {}
+                Some(false) => self.unsat = true,
+                None => self.enqueue(lits[0], None),
+            },
+            _ => {
+                self.watches[lits[0]].push(self.clauses.len());
+                self.watches[lits[1]].push(self.clauses.len());
+                self.clauses.push(lits);
+            }
+        }
+    }
+
+    fn decision_level(&self) -> usize {
+        self.trail_lim.len()
+    }
+
+    fn enqueue(&mut self, lit: usize, reason: Option<usize>) {
+        let var = lit >> 1;
+        self.assigns[var] = Some(lit & 1 == 0);
+        self.level[var] = self.decision_level();
+        self.reason[var] = reason;
+        self.trail.push(lit);
+    }
+
+    /// assigns everything the queued literals imply, returns a clause that became false
+    fn propagate(&mut self) -> Option<usize> {
+        while self.queue_head < self.trail.len() {
+            let false_lit = self.trail[self.queue_head] ^ 1;
+            self.queue_head += 1;
+            self.propagations += 1;
+
+            let mut watching = std::mem::take(&mut self.watches[false_lit]);
+            let mut conflict = None;
+            let mut i = 0;
+            while i < watching.len() {
+                let index = watching[i];
+                let clause = &mut self.clauses[index];
+                if clause[0] == false_lit {
+                    clause.swap(0, 1);
+                }
+                let first = clause[0];
+                if value(&self.assigns, first) == Some(true) {
+                    i += 1;
+                    continue;
+                }
+                if let Some(k) =
+                    (2..clause.len()).find(|k| value(&self.assigns, clause[*k]) != Some(false))
+                {
+                    clause.swap(1, k);
+                    self.watches[clause[1]].push(index);
+                    watching.swap_remove(i);
+                    continue;
+                }
+                if value(&self.assigns, first) == Some(false) {
+                    conflict = Some(index);
+                    break;
+                }
+                self.enqueue(first, Some(index));
+                i += 1;
+            }
+            self.watches[false_lit] = watching;
+            if conflict.is_some() {
+                self.queue_head = self.trail.len();
+                return conflict;
+            }
+        }
+        None
+    }
+
+    fn bump(&mut self, var: usize) {
+        self.activity[var] += self.activity_inc;
+        if self.activity[var] > 1e100 {
+            self.activity.iter_mut().for_each(|a| *a *= 1e-100);
+            self.activity_inc *= 1e-100;
+        }
+    }
+
+    /// the first-UIP clause learnt from `conflict`, asserting literal first, and the level to
+    /// jump back to
+    fn analyze(&mut self, mut conflict: usize) -> (Vec<usize>, usize) {
+        let mut learnt = vec![0];
+        let mut open = 0;
+        let mut index = self.trail.len();
+        let mut implied = None;
+        loop {
+            // a reason clause has the literal it implied first, that one is already handled
+            let skip = implied.is_some() as usize;
+            for lit in self.clauses[conflict].clone().into_iter().skip(skip) {
+                let var = lit >> 1;
+                if self.seen[var] || self.level[var] == 0 {
+                    continue;
+                }
+                self.bump(var);
+                self.seen[var] = true;
+                if self.level[var] == self.decision_level() {
+                    open += 1;
+                } else {
+                    learnt.push(lit);
+                }
+            }
+            loop {
+                index -= 1;
+                if self.seen[self.trail[index] >> 1] {
+                    break;
+                }
+            }
+            let lit = self.trail[index];
+            self.seen[lit >> 1] = false;
+            implied = Some(lit);
+            open -= 1;
+            if open == 0 {
+                break;
+            }
+            conflict = self.reason[lit >> 1].expect("only decisions have no reason");
+        }
+        learnt[0] = implied.unwrap() ^ 1;
+        for lit in &learnt[1..] {
+            self.seen[lit >> 1] = false;
+        }
+
+        // the literal of the highest remaining level goes second so it is watched
+        let mut jump = 0;
+        if let Some((i, _)) = learnt
+            .iter()
+            .enumerate()
+            .skip(1)
+            .max_by_key(|(_, lit)| self.level[*lit >> 1])
+        {
+            learnt.swap(1, i);
+            jump = self.level[learnt[1] >> 1];
+        }
+        (learnt, jump)
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if self.decision_level() <= level {
+            return;
+        }
+        let start = self.trail_lim[level];
+        for lit in self.trail.drain(start..) {
+            let var = lit >> 1;
+            self.phase[var] = lit & 1 == 0;
+            self.assigns[var] = None;
+            self.reason[var] = None;
+        }
+        self.trail_lim.truncate(level);
+        self.queue_head = start;
+    }
+
+    fn pick_branch(&self) -> Option<usize> {
+        (0..self.vars)
+            .filter(|v| self.assigns[*v].is_none())
+            .max_by(|a, b| self.activity[*a].total_cmp(&self.activity[*b]))
+            .map(|var| 2 * var + !self.phase[var] as usize)
+    }
+
+    /// searches for a model, `stop` is checked at every decision
+    pub fn solve(&mut self, stop: &dyn Fn() -> bool) -> SatResult {
+        if self.unsat {
+            return SatResult::Unsat;
+        }
+        let mut restarts = 0;
+        let mut conflicts_left = RESTART_BASE * luby(restarts);
+        loop {
+            if let Some(conflict) = self.propagate() {
+                self.conflicts += 1;
+                if self.decision_level() == 0 {
+                    self.unsat = true;
+                    return SatResult::Unsat;
+                }
+                let (learnt, jump) = self.analyze(conflict);
+                self.backtrack(jump);
+                if learnt.len() == 1 {
+                    self.enqueue(learnt[0], None);
+                } else {
+                    self.watches[learnt[0]].push(self.clauses.len());
+                    self.watches[learnt[1]].push(self.clauses.len());
+                    self.clauses.push(learnt);
+                    self.enqueue(
+                        self.clauses.last().unwrap()[0],
+                        Some(self.clauses.len() - 1),
+                    );
+                }
+                self.activity_inc /= ACTIVITY_DECAY;
+
+                conflicts_left -= 1;
+                if conflicts_left == 0 {
+                    restarts += 1;
+                    conflicts_left = RESTART_BASE * luby(restarts);
+                    self.backtrack(0);
+                }
+                continue;
+            }
+            if stop() {
+                return SatResult::Unknown;
+            }
+            match self.pick_branch() {
+                Some(lit) => {
+                    self.decisions += 1;
+                    self.trail_lim.push(self.trail.len());
+                    self.max_level = self.max_level.max(self.decision_level());
+                    self.enqueue(lit, None);
+                }
+                None => {
+                    return SatResult::Sat(self.assigns.iter().map(|a| a.unwrap()).collect());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solvers::cdcl::{Cdcl, SatResult};
+
+    #[test]
+    fn check_cdcl() {
+        // pigeonhole: 3 pigeons in 2 holes, p(i, h) = 2 * i + h + 1
+        let mut clauses = vec![];
+        for i in 0..3 {
+            clauses.push(vec![2 * i + 1, 2 * i + 2]);
+        }
+        for h in 1..=2 {
+            for i in 0..3 {
+                for j in i + 1..3 {
+                    clauses.push(vec![-(2 * i + h), -(2 * j + h)]);
+                }
+            }
+        }
+        assert_eq!(Cdcl::new(6, &clauses).solve(&|| false), SatResult::Unsat);
+
+        // dropping a pigeon makes it fit
+        let two = clauses
+            .iter()
+            .filter(|c| c.iter().all(|l| l.abs() <= 4))
+            .cloned()
+            .collect::<Vec<_>>();
+        match Cdcl::new(6, &two).solve(&|| false) {
+            SatResult::Sat(model) => {
+                for clause in &two {
+                    assert!(clause
+                        .iter()
+                        .any(|l| model[l.unsigned_abs() as usize - 1] == (*l > 0)));
+                }
+            }
+            other => panic!("expected a model, got {:?}", other),
+        }
+    }
+}
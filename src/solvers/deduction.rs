@@ -0,0 +1,949 @@
+use crate::data::board::Board;
+use crate::data::cage::Operator;
+use crate::data::cage_analysis::{derived_cages, forced_digit, Region};
+use crate::data::variant::Variant;
+use crate::solvers::backtracking::Backtracking;
+use crate::solvers::budget::{Budget, SolveOutcome};
+use std::time::Duration;
+
+/// (x, y)
+type Cell = (usize, usize);
+
+/// The pencil marks of a board: the digits every cell can still hold, narrowed down by
+/// deductions a person could make.
+///
+/// Every technique looks for one deduction that changes something and reports it as a
+/// `Deduction`, `apply` then carries it out. `deduce` keeps going until nothing applies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidates {
+    size: usize,
+    /// bit `d` is set when the cell can still hold `d`
+    marks: Vec<Vec<u32>>,
+    /// placed digits, 0 for empty cells
+    values: Vec<Vec<i32>>,
+    /// rows, columns and boxes
+    houses: Vec<(Region, Vec<Cell>)>,
+    peers: Vec<Vec<Vec<Cell>>>,
+    /// cages and derived cages that need different digits, with every digit combination
+    /// (as a mask) that meets their clue
+    cages: Vec<(Vec<Cell>, Vec<u32>)>,
+}
+
+/// The solving techniques, roughly from easy to hard.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Technique {
+    /// a cell with one candidate left
+    NakedSingle,
+    /// a digit with one place left in a house
+    HiddenSingle,
+    /// digits that fit no combination of their cage
+    CageCombination,
+    NakedPair,
+    HiddenPair,
+    /// a digit whose places in a box share a row or column, so the rest of it can't have it
+    Pointing,
+    /// a digit whose places in a row or column share a box, so the rest of the box can't have it
+    BoxLine,
+    /// a digit every combination of a cage uses, whose places in the cage share a house, so
+    /// the rest of that house can't have it
+    CageLockedSet,
+    NakedTriple,
+    HiddenTriple,
+    XWing,
+    Swordfish,
+}
+
+/// One step of reasoning and what it changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deduction {
+    pub technique: Technique,
+    /// the cells the reasoning is about
+    pub cells: Vec<Cell>,
+    /// the digits the reasoning is about
+    pub digits: Vec<i32>,
+    /// (x, y, digit) to fill in
+    pub placements: Vec<(usize, usize, i32)>,
+    /// (x, y, digit) to cross out
+    pub eliminations: Vec<(usize, usize, i32)>,
+}
+
+/// How hard a puzzle is to solve by hand, from the techniques it needs.
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub enum Grade {
+    /// singles and cage combinations
+    Easy,
+    /// pairs and locked candidates
+    Medium,
+    /// triples, cage locked sets and fish
+    Hard,
+    /// the techniques get stuck, some guessing is needed
+    Extreme,
+}
+
+/// Everything `deduce` found, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Logic {
+    pub steps: Vec<Deduction>,
+    /// the board with every placed digit
+    pub board: Board,
+    pub solved: bool,
+}
+
+impl Technique {
+    pub const ALL: [Technique; 12] = [
+        Technique::NakedSingle,
+        Technique::HiddenSingle,
+        Technique::CageCombination,
+        Technique::NakedPair,
+        Technique::HiddenPair,
+        Technique::Pointing,
+        Technique::BoxLine,
+        Technique::CageLockedSet,
+        Technique::NakedTriple,
+        Technique::HiddenTriple,
+        Technique::XWing,
+        Technique::Swordfish,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Technique::NakedSingle => "naked single",
+            Technique::HiddenSingle => "hidden single",
+            Technique::CageCombination => "cage combination",
+            Technique::NakedPair => "naked pair",
+            Technique::HiddenPair => "hidden pair",
+            Technique::Pointing => "pointing",
+            Technique::BoxLine => "box/line reduction",
+            Technique::CageLockedSet => "cage locked set",
+            Technique::NakedTriple => "naked triple",
+            Technique::HiddenTriple => "hidden triple",
+            Technique::XWing => "x-wing",
+            Technique::Swordfish => "swordfish",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|t| t.name() == name).copied()
+    }
+    /// how hard the technique is to spot, for grading
+    pub fn difficulty(&self) -> u32 {
+        match self {
+            Technique::NakedSingle | Technique::HiddenSingle => 1,
+            Technique::CageCombination => 2,
+            Technique::NakedPair
+            | Technique::HiddenPair
+            | Technique::Pointing
+            | Technique::BoxLine => 3,
+            Technique::CageLockedSet | Technique::NakedTriple => 4,
+            Technique::HiddenTriple => 5,
+            Technique::XWing => 6,
+            Technique::Swordfish => 7,
+        }
+    }
+}
+
+impl Grade {
+    pub const ALL: [Grade; 4] = [Grade::Easy, Grade::Medium, Grade::Hard, Grade::Extreme];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Grade::Easy => "easy",
+            Grade::Medium => "medium",
+            Grade::Hard => "hard",
+            Grade::Extreme => "extreme",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|g| g.name() == name).copied()
+    }
+}
+
+impl Deduction {
+    fn new(technique: Technique, cells: Vec<Cell>, digits: Vec<i32>) -> Self {
+        Self {
+            technique,
+            cells,
+            digits,
+            placements: vec![],
+            eliminations: vec![],
+        }
+    }
+
+    /// a sentence for hints, e.g. "naked pair of [1, 2] in [(0, 0), (1, 0)] removes 1 from (3, 0)"
+    pub fn describe(&self) -> String {
+        let mut out = format!(
+            "{} of {:?} in {:?}",
+            self.technique.name(),
+            self.digits,
+            self.cells
+        );
+        for (x, y, num) in &self.placements {
+            out.push_str(&format!(" places {} in ({}, {})", num, x, y));
+        }
+        for (x, y, num) in &self.eliminations {
+            out.push_str(&format!(" removes {} from ({}, {})", num, x, y));
+        }
+        out
+    }
+}
+
+impl Logic {
+    /// the hardest technique the puzzle needed so far, `None` if nothing was deduced
+    pub fn hardest(&self) -> Option<Technique> {
+        self.steps
+            .iter()
+            .map(|s| s.technique)
+            .max_by_key(|t| t.difficulty())
+    }
+
+    /// the grade of the puzzle, extreme unless the deductions solved it
+    pub fn grade(&self) -> Grade {
+        if !self.solved {
+            return Grade::Extreme;
+        }
+        match self.hardest().map_or(0, |t| t.difficulty()) {
+            0..=2 => Grade::Easy,
+            3 => Grade::Medium,
+            _ => Grade::Hard,
+        }
+    }
+}
+
+fn digits(mask: u32) -> Vec<i32> {
+    (1..32).filter(|d| mask & (1 << d) != 0).collect()
+}
+
+/// every way to pick `n` of `items`, in order
+fn choose<T: Copy>(items: &[T], n: usize) -> Vec<Vec<T>> {
+    if n == 0 {
+        return vec![vec![]];
+    }
+    let mut out = vec![];
+    for (i, item) in items.iter().enumerate() {
+        for mut rest in choose(&items[i + 1..], n - 1) {
+            rest.insert(0, *item);
+            out.push(rest);
+        }
+    }
+    out
+}
+
+/// whether every cell can get its own digit from its `allowed` mask (Kuhn's matching)
+fn has_matching(allowed: &[u32]) -> bool {
+    fn augment(cell: usize, allowed: &[u32], owner: &mut [Option<usize>], seen: &mut u32) -> bool {
+        for d in digits(allowed[cell] & !*seen) {
+            *seen |= 1 << d;
+            let d = d as usize;
+            if owner[d].is_none_or(|other| augment(other, allowed, owner, seen)) {
+                owner[d] = Some(cell);
+                return true;
+            }
+        }
+        false
+    }
+    let mut owner = vec![None; 32];
+    (0..allowed.len()).all(|cell| augment(cell, allowed, &mut owner, &mut 0))
+}
+
+impl Candidates {
+    pub fn new(board: &Board) -> Self {
+        let size = board.get_size();
+        let (box_width, box_height) = board.get_box_size();
+        let values = (0..size)
+            .map(|y| (0..size).map(|x| board.get_value(x, y)).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let marks = (0..size)
+            .map(|y| {
+                (0..size)
+                    .map(|x| match values[y][x] {
+                        0 => (1..=size as i32)
+                            .filter(|d| board.is_possible(x, y, *d))
+                            .fold(0, |mask, d| mask | 1 << d),
+                        num => 1 << num,
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut houses = vec![];
+        for i in 0..size {
+            let (box_x, box_y) = (
+                (i % (size / box_width)) * box_width,
+                (i / (size / box_width)) * box_height,
+            );
+            houses.push((Region::Row(i), (0..size).map(|x| (x, i)).collect()));
+            houses.push((Region::Column(i), (0..size).map(|y| (i, y)).collect()));
+            houses.push((
+                Region::Box(i),
+                (0..size)
+                    .map(|o| (box_x + o % box_width, box_y + o / box_width))
+                    .collect(),
+            ));
+        }
+        let peers = (0..size)
+            .map(|y| (0..size).map(|x| board.get_peers(x, y)).collect())
+            .collect();
+
+        let mut cages = vec![];
+        if !board.get_variants().contains(&Variant::RepeatingCages) {
+            let mut seen = vec![];
+            for y in 0..size {
+                for x in 0..size {
+                    let (index, op, clue) = (
+                        board.get_cage_index(x, y),
+                        board.get_operator(x, y),
+                        board.get_sum(x, y),
+                    );
+                    if index != 0
+                        && clue > 0
+                        && op != Operator::Unlabelled
+                        && !seen.contains(&index)
+                    {
+                        seen.push(index);
+                        let cells = board.get_cage_cells(x, y);
+                        cages.push((cells.clone(), combinations(size, cells.len(), op, clue)));
+                    }
+                }
+            }
+            for cage in derived_cages(board) {
+                let combos = combinations(size, cage.cells.len(), Operator::Sum, cage.sum);
+                cages.push((cage.cells, combos));
+            }
+        }
+
+        Self {
+            size,
+            marks,
+            values,
+            houses,
+            peers,
+            cages,
+        }
+    }
+
+    /// the digits (x, y) can still hold
+    pub fn get(&self, x: usize, y: usize) -> Vec<i32> {
+        digits(self.marks[y][x])
+    }
+
+    fn empty(&self, (x, y): Cell) -> bool {
+        self.values[y][x] == 0
+    }
+    fn has(&self, (x, y): Cell, d: i32) -> bool {
+        self.values[y][x] == 0 && self.marks[y][x] & (1 << d) != 0
+    }
+    /// the empty cells of `cells` that can hold `d`
+    fn places(&self, cells: &[Cell], d: i32) -> Vec<Cell> {
+        cells.iter().filter(|c| self.has(**c, d)).copied().collect()
+    }
+    /// crossing the digits of `mask` out of the empty `cells`
+    fn eliminations(&self, cells: &[Cell], mask: u32) -> Vec<(usize, usize, i32)> {
+        let mut out = vec![];
+        for (x, y) in cells {
+            if self.empty((*x, *y)) {
+                for d in digits(self.marks[*y][*x] & mask) {
+                    out.push((*x, *y, d));
+                }
+            }
+        }
+        out
+    }
+
+    /// an empty cell without candidates, the board has a mistake
+    pub fn is_broken(&self) -> bool {
+        (0..self.size * self.size).any(|i| {
+            let (x, y) = (i % self.size, i / self.size);
+            self.empty((x, y)) && self.marks[y][x] == 0
+        })
+    }
+    pub fn is_solved(&self) -> bool {
+        self.values.iter().flatten().all(|v| *v != 0)
+    }
+
+    /// the first deduction of the easiest technique that changes something
+    pub fn next_deduction(&self) -> Option<Deduction> {
+        if self.is_broken() {
+            return None;
+        }
+        Technique::ALL.iter().find_map(|t| self.find(*t))
+    }
+
+    /// the first deduction `technique` makes that changes something
+    pub fn find(&self, technique: Technique) -> Option<Deduction> {
+        match technique {
+            Technique::NakedSingle => self.naked_single(),
+            Technique::HiddenSingle => self.hidden_single(),
+            Technique::CageCombination => self.cage_combination(),
+            Technique::NakedPair => self.naked_subset(technique, 2),
+            Technique::NakedTriple => self.naked_subset(technique, 3),
+            Technique::HiddenPair => self.hidden_subset(technique, 2),
+            Technique::HiddenTriple => self.hidden_subset(technique, 3),
+            Technique::Pointing => self.locked(technique, true),
+            Technique::BoxLine => self.locked(technique, false),
+            Technique::CageLockedSet => self.cage_locked_set(),
+            Technique::XWing => self.fish(technique, 2),
+            Technique::Swordfish => self.fish(technique, 3),
+        }
+    }
+
+    pub fn apply(&mut self, deduction: &Deduction) {
+        for (x, y, num) in &deduction.placements {
+            self.values[*y][*x] = *num;
+            self.marks[*y][*x] = 1 << num;
+            for (px, py) in &self.peers[*y][*x] {
+                if self.values[*py][*px] == 0 {
+                    self.marks[*py][*px] &= !(1 << num);
+                }
+            }
+        }
+        for (x, y, num) in &deduction.eliminations {
+            self.marks[*y][*x] &= !(1 << num);
+        }
+    }
+
+    /// `board` with every digit placed so far
+    pub fn to_board(&self, board: &Board) -> Board {
+        let mut out = board.clone();
+        for y in 0..self.size {
+            for x in 0..self.size {
+                out.unchecked_set_num(x, y, self.values[y][x]);
+            }
+        }
+        out
+    }
+
+    fn naked_single(&self) -> Option<Deduction> {
+        (0..self.size * self.size).find_map(|i| {
+            let (x, y) = (i % self.size, i / self.size);
+            let mask = self.marks[y][x];
+            (self.empty((x, y)) && mask.count_ones() == 1).then(|| {
+                let num = digits(mask)[0];
+                let mut step = Deduction::new(Technique::NakedSingle, vec![(x, y)], vec![num]);
+                step.placements.push((x, y, num));
+                step
+            })
+        })
+    }
+
+    fn hidden_single(&self) -> Option<Deduction> {
+        for (_, cells) in &self.houses {
+            for d in 1..=self.size as i32 {
+                let places = self.places(cells, d);
+                if let [(x, y)] = places[..] {
+                    let mut step = Deduction::new(Technique::HiddenSingle, cells.clone(), vec![d]);
+                    step.placements.push((x, y, d));
+                    return Some(step);
+                }
+            }
+        }
+        None
+    }
+
+    fn naked_subset(&self, technique: Technique, n: usize) -> Option<Deduction> {
+        for (_, cells) in &self.houses {
+            let open = cells
+                .iter()
+                .filter(|(x, y)| {
+                    let count = self.marks[*y][*x].count_ones() as usize;
+                    self.empty((*x, *y)) && (2..=n).contains(&count)
+                })
+                .copied()
+                .collect::<Vec<_>>();
+            for subset in choose(&open, n) {
+                let mask = subset
+                    .iter()
+                    .fold(0, |mask, (x, y)| mask | self.marks[*y][*x]);
+                if mask.count_ones() as usize != n {
+                    continue;
+                }
+                let rest = cells
+                    .iter()
+                    .filter(|c| !subset.contains(c))
+                    .copied()
+                    .collect::<Vec<_>>();
+                let eliminations = self.eliminations(&rest, mask);
+                if !eliminations.is_empty() {
+                    let mut step = Deduction::new(technique, subset, digits(mask));
+                    step.eliminations = eliminations;
+                    return Some(step);
+                }
+            }
+        }
+        None
+    }
+
+    fn hidden_subset(&self, technique: Technique, n: usize) -> Option<Deduction> {
+        for (_, cells) in &self.houses {
+            let open = (1..=self.size as i32)
+                .filter(|d| (2..=n).contains(&self.places(cells, *d).len()))
+                .collect::<Vec<_>>();
+            for subset in choose(&open, n) {
+                let mut places = subset
+                    .iter()
+                    .flat_map(|d| self.places(cells, *d))
+                    .collect::<Vec<_>>();
+                places.sort_unstable();
+                places.dedup();
+                if places.len() != n {
+                    continue;
+                }
+                let mask = subset.iter().fold(0, |mask, d| mask | 1 << d);
+                let eliminations = self.eliminations(&places, !mask);
+                if !eliminations.is_empty() {
+                    let mut step = Deduction::new(technique, places, subset);
+                    step.eliminations = eliminations;
+                    return Some(step);
+                }
+            }
+        }
+        None
+    }
+
+    /// pointing when `from_box`, box/line reduction otherwise
+    fn locked(&self, technique: Technique, from_box: bool) -> Option<Deduction> {
+        for (region, cells) in &self.houses {
+            if matches!(region, Region::Box(_)) != from_box {
+                continue;
+            }
+            for d in 1..=self.size as i32 {
+                let places = self.places(cells, d);
+                if places.len() < 2 {
+                    continue;
+                }
+                // every other house holding all the places
+                for (_, other) in &self.houses {
+                    if other == cells || !places.iter().all(|p| other.contains(p)) {
+                        continue;
+                    }
+                    let rest = other
+                        .iter()
+                        .filter(|c| !cells.contains(c))
+                        .copied()
+                        .collect::<Vec<_>>();
+                    let eliminations = self.eliminations(&rest, 1 << d);
+                    if !eliminations.is_empty() {
+                        let mut step = Deduction::new(technique, places, vec![d]);
+                        step.eliminations = eliminations;
+                        return Some(step);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn fish(&self, technique: Technique, n: usize) -> Option<Deduction> {
+        let size = self.size;
+        for d in 1..=size as i32 {
+            for by_rows in [true, false] {
+                // the cell on line `line` and cross line `cross`
+                let cell = |line: usize, cross: usize| match by_rows {
+                    true => (cross, line),
+                    false => (line, cross),
+                };
+                let crosses = |line: usize| {
+                    (0..size)
+                        .filter(|cross| self.has(cell(line, *cross), d))
+                        .collect::<Vec<_>>()
+                };
+                let lines = (0..size)
+                    .filter(|line| (2..=n).contains(&crosses(*line).len()))
+                    .collect::<Vec<_>>();
+                for subset in choose(&lines, n) {
+                    let mut covered = subset.iter().flat_map(|l| crosses(*l)).collect::<Vec<_>>();
+                    covered.sort_unstable();
+                    covered.dedup();
+                    if covered.len() != n {
+                        continue;
+                    }
+                    let rest = (0..size)
+                        .filter(|line| !subset.contains(line))
+                        .flat_map(|line| covered.iter().map(move |cross| (line, *cross)))
+                        .map(|(line, cross)| cell(line, cross))
+                        .collect::<Vec<_>>();
+                    let eliminations = self.eliminations(&rest, 1 << d);
+                    if !eliminations.is_empty() {
+                        let cells = subset
+                            .iter()
+                            .flat_map(|line| covered.iter().map(move |cross| (*line, *cross)))
+                            .map(|(line, cross)| cell(line, cross))
+                            .filter(|c| self.has(*c, d))
+                            .collect();
+                        let mut step = Deduction::new(technique, cells, vec![d]);
+                        step.eliminations = eliminations;
+                        return Some(step);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// the combinations of a cage that can still be placed in its cells
+    fn valid_combinations(&self, cells: &[Cell], combinations: &[u32]) -> Vec<u32> {
+        combinations
+            .iter()
+            .filter(|combo| {
+                let allowed = cells
+                    .iter()
+                    .map(|(x, y)| self.marks[*y][*x] & **combo)
+                    .collect::<Vec<_>>();
+                has_matching(&allowed)
+            })
+            .copied()
+            .collect()
+    }
+
+    fn cage_combination(&self) -> Option<Deduction> {
+        for (cells, combinations) in &self.cages {
+            let valid = self.valid_combinations(cells, combinations);
+            let mut eliminations = vec![];
+            for (i, (x, y)) in cells.iter().enumerate() {
+                if !self.empty((*x, *y)) {
+                    continue;
+                }
+                for d in digits(self.marks[*y][*x]) {
+                    // some combination has to put `d` here and still fill the other cells
+                    let supported = valid.iter().any(|combo| {
+                        let allowed = cells
+                            .iter()
+                            .enumerate()
+                            .map(|(j, (cx, cy))| match i == j {
+                                true => *combo & (1 << d),
+                                false => self.marks[*cy][*cx] & *combo,
+                            })
+                            .collect::<Vec<_>>();
+                        has_matching(&allowed)
+                    });
+                    if !supported {
+                        eliminations.push((*x, *y, d));
+                    }
+                }
+            }
+            if !eliminations.is_empty() {
+                let union = valid.iter().fold(0, |mask, c| mask | c);
+                let mut step =
+                    Deduction::new(Technique::CageCombination, cells.clone(), digits(union));
+                step.eliminations = eliminations;
+                return Some(step);
+            }
+        }
+        None
+    }
+
+    fn cage_locked_set(&self) -> Option<Deduction> {
+        for (cells, combinations) in &self.cages {
+            let valid = self.valid_combinations(cells, combinations);
+            let required = valid.iter().fold(u32::MAX, |mask, c| mask & c);
+            if valid.is_empty() {
+                continue;
+            }
+            for d in digits(required) {
+                let places = self.places(cells, d);
+                if places.is_empty() {
+                    continue;
+                }
+                for (_, house) in &self.houses {
+                    if !places.iter().all(|p| house.contains(p)) {
+                        continue;
+                    }
+                    let rest = house
+                        .iter()
+                        .filter(|c| !cells.contains(c))
+                        .copied()
+                        .collect::<Vec<_>>();
+                    let eliminations = self.eliminations(&rest, 1 << d);
+                    if !eliminations.is_empty() {
+                        let mut step =
+                            Deduction::new(Technique::CageLockedSet, cells.clone(), vec![d]);
+                        step.eliminations = eliminations;
+                        return Some(step);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// every set of `len` different digits up to `size` (as a mask) that `op` turns into `clue`
+fn combinations(size: usize, len: usize, op: Operator, clue: i32) -> Vec<u32> {
+    let mut out = vec![];
+    push_masks(&mut out, 0, 1, size as i32, len, op, clue);
+    out
+}
+
+/// adds the digits from `next` on to `mask` until it holds `left` more, like
+/// `push_combinations` of the cage table. sums stop early once even the smallest digits left
+/// overshoot the clue
+fn push_masks(
+    out: &mut Vec<u32>,
+    mask: u32,
+    next: i32,
+    size: i32,
+    left: usize,
+    op: Operator,
+    clue: i32,
+) {
+    if left == 0 {
+        if op.evaluate(&digits(mask)) == Some(clue) {
+            out.push(mask);
+        }
+        return;
+    }
+    for digit in next..=size - left as i32 + 1 {
+        if op == Operator::Sum {
+            // the digits so far, this one and the smallest that could follow it
+            let smallest = (digit..digit + left as i32).sum::<i32>();
+            if digits(mask).iter().sum::<i32>() + smallest > clue {
+                break;
+            }
+        }
+        push_masks(out, mask | 1 << digit, digit + 1, size, left - 1, op, clue);
+    }
+}
+
+/// applies the easiest deduction until none is left
+pub fn deduce(board: &Board) -> Logic {
+    let mut candidates = Candidates::new(board);
+    let mut steps = vec![];
+    while let Some(step) = candidates.next_deduction() {
+        candidates.apply(&step);
+        steps.push(step);
+    }
+    Logic {
+        board: candidates.to_board(board),
+        solved: candidates.is_solved() && !candidates.is_broken(),
+        steps,
+    }
+}
+
+/// the digit of (x, y) with the deductions that lead to it, `None` if they don't get there
+pub fn hint(board: &Board, x: usize, y: usize) -> Option<(i32, Vec<Deduction>)> {
+    let mut candidates = Candidates::new(board);
+    let mut steps = vec![];
+    while candidates.values[y][x] == 0 {
+        let step = candidates.next_deduction()?;
+        candidates.apply(&step);
+        steps.push(step);
+    }
+    Some((candidates.values[y][x], steps))
+}
+
+/// the digit of (x, y) and why, found the way a person would: a derived cage with one empty
+/// cell left gives it away, then the deductions, only then a solver searching for `time`
+pub fn explain_hint(
+    board: &Board,
+    x: usize,
+    y: usize,
+    time: Duration,
+) -> Result<(i32, Vec<String>), String> {
+    if let Some((num, cage)) = forced_digit(board, x, y) {
+        let reason = format!(
+            "{:?} add up to {} ({:?} minus its cages)",
+            cage.cells, cage.sum, cage.source
+        );
+        return Ok((num, vec![reason]));
+    }
+    if let Some((num, steps)) = hint(board, x, y) {
+        return Ok((num, steps.iter().map(|step| step.describe()).collect()));
+    }
+    let budget = Budget::new().with_time(time);
+    match Backtracking::new().solve_within(board, &budget).0 {
+        SolveOutcome::Solved(solution) => Ok((solution.get_value(x, y), vec![])),
+        SolveOutcome::Unsolvable => Err("the board has a mistake".to_string()),
+        SolveOutcome::GaveUp(_) => Err("the solver gave up".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::board::Board;
+    use crate::data::cage::Operator;
+    use crate::solvers::backtracking::Backtracking;
+    use crate::solvers::deduction::{
+        combinations, deduce, Candidates, Cell, Deduction, Grade, Technique,
+    };
+
+    /// an empty 9x9 where every cell can hold anything, except `cells` which only keep `keep`
+    fn candidates(changes: &[(&[Cell], &[i32])]) -> Candidates {
+        let mut candidates = Candidates::new(&Board::new(9));
+        for (cells, keep) in changes {
+            let mask = keep.iter().fold(0, |mask, d| mask | 1 << d);
+            for (x, y) in *cells {
+                candidates.marks[*y][*x] = mask;
+            }
+        }
+        candidates
+    }
+
+    /// the cells of row `y` from column `from` on
+    fn row(y: usize, from: usize) -> Vec<Cell> {
+        (from..9).map(|x| (x, y)).collect()
+    }
+
+    fn step(
+        technique: Technique,
+        cells: Vec<Cell>,
+        digits: Vec<i32>,
+        eliminations: Vec<(usize, usize, i32)>,
+    ) -> Option<Deduction> {
+        let mut step = Deduction::new(technique, cells, digits);
+        step.eliminations = eliminations;
+        Some(step)
+    }
+
+    const ALL_BUT_5: &[i32] = &[1, 2, 3, 4, 6, 7, 8, 9];
+
+    #[test]
+    fn check_deduction() {
+        let board = Board::sample_puzzle();
+        let logic = deduce(&board);
+        assert!(logic.solved);
+        assert!(logic.hardest().is_some());
+        assert_eq!(logic.grade(), Grade::Easy);
+        assert_eq!(Some(logic.board), Backtracking::new().solve(&board));
+
+        // a 3 in two cells locks 1 and 2 out of the rest of their row and box
+        let text = "size 4\ncages\n1 1 0 0\n0 0 0 0\n0 0 0 0\n0 0 0 0\nsums\n1 3\n";
+        let board = Board::parse(text).unwrap();
+        let candidates = Candidates::new(&board);
+        assert_eq!(candidates.get(0, 0), vec![1, 2]);
+        let step = candidates.find(Technique::CageLockedSet).unwrap();
+        assert_eq!(step.digits, vec![1]);
+        assert!(step.eliminations.contains(&(2, 0, 1)));
+        assert_eq!(Technique::from_name("x-wing"), Some(Technique::XWing));
+
+        // only the sets of the right length are looked at, so 25x25 cages stay quick
+        assert_eq!(combinations(25, 3, Operator::Sum, 6), vec![0b1110]);
+        assert_eq!(combinations(25, 2, Operator::Sum, 49).len(), 1);
+    }
+
+    #[test]
+    fn check_subsets() {
+        // (0, 0) and (1, 0) can only hold 1 and 2, the rest of the row can't
+        let pair = candidates(&[(&[(0, 0), (1, 0)], &[1, 2])]);
+        let removed = row(0, 2)
+            .into_iter()
+            .flat_map(|(x, y)| [(x, y, 1), (x, y, 2)]);
+        assert_eq!(
+            pair.find(Technique::NakedPair),
+            step(
+                Technique::NakedPair,
+                vec![(0, 0), (1, 0)],
+                vec![1, 2],
+                removed.collect()
+            )
+        );
+
+        let triple = candidates(&[
+            (&[(0, 0)], &[1, 2]),
+            (&[(1, 0)], &[2, 3]),
+            (&[(2, 0)], &[1, 3]),
+        ]);
+        let removed = row(0, 3)
+            .into_iter()
+            .flat_map(|(x, y)| [(x, y, 1), (x, y, 2), (x, y, 3)]);
+        assert_eq!(
+            triple.find(Technique::NakedTriple),
+            step(
+                Technique::NakedTriple,
+                vec![(0, 0), (1, 0), (2, 0)],
+                vec![1, 2, 3],
+                removed.collect()
+            )
+        );
+
+        // 1 and 2 only fit (0, 0) and (1, 0) of the row, so those can't hold anything else
+        let hidden = candidates(&[(&row(0, 2), &[3, 4, 5, 6, 7, 8, 9])]);
+        let removed = row(0, 0)[..2]
+            .iter()
+            .flat_map(|(x, y)| (3..=9).map(move |d| (*x, *y, d)))
+            .collect();
+        assert_eq!(
+            hidden.find(Technique::HiddenPair),
+            step(
+                Technique::HiddenPair,
+                vec![(0, 0), (1, 0)],
+                vec![1, 2],
+                removed
+            )
+        );
+
+        let hidden = candidates(&[(&row(0, 3), &[4, 5, 6, 7, 8, 9])]);
+        let removed = row(0, 0)[..3]
+            .iter()
+            .flat_map(|(x, y)| (4..=9).map(move |d| (*x, *y, d)))
+            .collect();
+        assert_eq!(
+            hidden.find(Technique::HiddenTriple),
+            step(
+                Technique::HiddenTriple,
+                vec![(0, 0), (1, 0), (2, 0)],
+                vec![1, 2, 3],
+                removed
+            )
+        );
+    }
+
+    #[test]
+    fn check_locked() {
+        // the 5 of the top left box is in its first row, so not in the rest of that row
+        let box_cells = [(2, 0), (0, 1), (1, 1), (2, 1), (0, 2), (1, 2), (2, 2)];
+        let pointing = candidates(&[(&box_cells, ALL_BUT_5)]);
+        let removed = row(0, 3).into_iter().map(|(x, y)| (x, y, 5)).collect();
+        assert_eq!(
+            pointing.find(Technique::Pointing),
+            step(Technique::Pointing, vec![(0, 0), (1, 0)], vec![5], removed)
+        );
+
+        // the 5 of the first row is in the top left box, so not in the rest of that box
+        let box_line = candidates(&[(&row(0, 2), ALL_BUT_5)]);
+        let removed = box_cells[1..].iter().map(|(x, y)| (*x, *y, 5)).collect();
+        assert_eq!(
+            box_line.find(Technique::BoxLine),
+            step(Technique::BoxLine, vec![(0, 0), (1, 0)], vec![5], removed)
+        );
+    }
+
+    #[test]
+    fn check_fish() {
+        // the 5s of rows 0 and 4 are in columns 1 and 6, so not in the rest of those columns
+        let others = |rows: &[usize], columns: &[usize]| {
+            rows.iter()
+                .flat_map(|y| (0..9).map(move |x| (x, *y)))
+                .filter(|(x, _)| !columns.contains(x))
+                .collect::<Vec<_>>()
+        };
+        let removed = |rows: &[usize], columns: &[usize]| {
+            (0..9)
+                .filter(|y| !rows.contains(y))
+                .flat_map(|y| columns.iter().map(move |x| (*x, y, 5)))
+                .collect::<Vec<_>>()
+        };
+        let x_wing = candidates(&[(&others(&[0, 4], &[1, 6]), ALL_BUT_5)]);
+        assert_eq!(
+            x_wing.find(Technique::XWing),
+            step(
+                Technique::XWing,
+                vec![(1, 0), (6, 0), (1, 4), (6, 4)],
+                vec![5],
+                removed(&[0, 4], &[1, 6])
+            )
+        );
+
+        let swordfish = candidates(&[
+            (&others(&[0], &[1, 4]), ALL_BUT_5),
+            (&others(&[3], &[4, 7]), ALL_BUT_5),
+            (&others(&[6], &[1, 7]), ALL_BUT_5),
+        ]);
+        assert_eq!(
+            swordfish.find(Technique::Swordfish),
+            step(
+                Technique::Swordfish,
+                vec![(1, 0), (4, 0), (4, 3), (7, 3), (1, 6), (7, 6)],
+                vec![5],
+                removed(&[0, 3, 6], &[1, 4, 7])
+            )
+        );
+    }
+}
@@ -0,0 +1,395 @@
+use crate::data::board::Board;
+use crate::solvers::box_filling::{swap, BoxRules, Grid};
+use crate::solvers::budget::{Budget, Limit, SolveOutcome};
+use crate::solvers::stats::{board_bytes, SolveStats};
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fmt::Write;
+
+/// Evolutionary solver: every individual fills each box with a permutation of its missing
+/// digits, so only rows, columns, cages and variants can be wrong.
+///
+/// Fitness is the same cost as `LocalSearch`, repeated digits in rows and columns plus how far
+/// cages are from their clue, lower is better. Like local search it can't prove a puzzle has no
+/// solution, running out of generations gives up.
+#[derive(Debug, Clone)]
+pub struct Genetic {
+    seed: u64,
+    population: usize,
+    generations: usize,
+    /// the best individuals copied unchanged into the next generation
+    elitism: usize,
+    /// chance of mutating each child
+    mutation_rate: f64,
+    /// generations without a new best cost before everyone but the elite is replaced
+    restart_after: usize,
+    selection: Selection,
+    crossover: Crossover,
+    mutation: Mutation,
+}
+
+/// How parents are picked from the population.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum Selection {
+    /// the best of `TOURNAMENT_SIZE` random individuals
+    #[default]
+    Tournament,
+    /// chance proportional to `1 / (1 + cost)`
+    Roulette,
+    /// chance proportional to the place in the population, best first
+    Rank,
+}
+
+/// How two parents are combined, always whole boxes so children keep valid boxes.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum Crossover {
+    /// every box from a random parent
+    #[default]
+    Uniform,
+    /// the boxes before a random cut from the first parent, the rest from the second
+    OnePoint,
+}
+
+/// How a child is changed.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum Mutation {
+    /// swaps two free cells of one box
+    #[default]
+    Swap,
+    /// reshuffles the free cells of one box
+    Shuffle,
+}
+
+/// The costs of every generation of one run, for the report.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Evolution {
+    pub generations: Vec<GenerationStats>,
+    /// generations at which the population was replaced
+    pub restarts: Vec<usize>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GenerationStats {
+    pub generation: usize,
+    pub best: usize,
+    pub mean: f64,
+    pub worst: usize,
+}
+
+const TOURNAMENT_SIZE: usize = 3;
+
+impl Selection {
+    pub const ALL: [Selection; 3] = [Selection::Tournament, Selection::Roulette, Selection::Rank];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Selection::Tournament => "tournament",
+            Selection::Roulette => "roulette",
+            Selection::Rank => "rank",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|s| s.name() == name).copied()
+    }
+
+    /// the index of a parent in `costs`, which are sorted best first
+    fn pick(&self, costs: &[usize], rng: &mut StdRng) -> usize {
+        match self {
+            Selection::Tournament => (0..TOURNAMENT_SIZE)
+                .map(|_| rng.gen_range(0..costs.len()))
+                .min()
+                .unwrap(),
+            Selection::Roulette => {
+                let weights = costs.iter().map(|c| 1. / (1. + *c as f64));
+                WeightedIndex::new(weights).unwrap().sample(rng)
+            }
+            Selection::Rank => {
+                let weights = (1..=costs.len()).rev();
+                WeightedIndex::new(weights).unwrap().sample(rng)
+            }
+        }
+    }
+}
+
+impl Crossover {
+    pub const ALL: [Crossover; 2] = [Crossover::Uniform, Crossover::OnePoint];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Crossover::Uniform => "uniform",
+            Crossover::OnePoint => "one-point",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|c| c.name() == name).copied()
+    }
+
+    fn cross(&self, rules: &BoxRules, a: &Grid, b: &Grid, rng: &mut StdRng) -> Grid {
+        let mut child = a.clone();
+        let cut = rng.gen_range(0..=rules.boxes.len());
+        for (i, cells) in rules.boxes.iter().enumerate() {
+            let from_b = match self {
+                Crossover::Uniform => rng.gen_bool(0.5),
+                Crossover::OnePoint => i >= cut,
+            };
+            if from_b {
+                for (x, y) in cells {
+                    child[*y][*x] = b[*y][*x];
+                }
+            }
+        }
+        child
+    }
+}
+
+impl Mutation {
+    pub const ALL: [Mutation; 2] = [Mutation::Swap, Mutation::Shuffle];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mutation::Swap => "swap",
+            Mutation::Shuffle => "shuffle",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|m| m.name() == name).copied()
+    }
+
+    fn mutate(&self, rules: &BoxRules, grid: &mut Grid, rng: &mut StdRng) {
+        match self {
+            Mutation::Swap => {
+                if let Some((a, b)) = rules.random_swap(rng) {
+                    swap(grid, a, b);
+                }
+            }
+            Mutation::Shuffle => {
+                let i = rng.gen_range(0..rules.boxes.len());
+                rules.shuffle_box(grid, i, rng);
+            }
+        }
+    }
+}
+
+impl Default for Genetic {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl Genetic {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            population: 200,
+            generations: 5_000,
+            elitism: 10,
+            mutation_rate: 0.5,
+            restart_after: 200,
+            selection: Selection::default(),
+            crossover: Crossover::default(),
+            mutation: Mutation::default(),
+        }
+    }
+    pub fn with_population(mut self, population: usize) -> Self {
+        self.population = population.max(2);
+        self
+    }
+    pub fn with_generations(mut self, generations: usize) -> Self {
+        self.generations = generations;
+        self
+    }
+    pub fn with_elitism(mut self, elitism: usize) -> Self {
+        self.elitism = elitism;
+        self
+    }
+    pub fn with_mutation_rate(mut self, rate: f64) -> Self {
+        self.mutation_rate = rate;
+        self
+    }
+    pub fn with_restart_after(mut self, generations: usize) -> Self {
+        self.restart_after = generations;
+        self
+    }
+    pub fn with_selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+    pub fn with_crossover(mut self, crossover: Crossover) -> Self {
+        self.crossover = crossover;
+        self
+    }
+    pub fn with_mutation(mut self, mutation: Mutation) -> Self {
+        self.mutation = mutation;
+        self
+    }
+
+    pub fn name(&self) -> String {
+        format!(
+            "genetic ({} + {} + {}, seed {})",
+            self.selection.name(),
+            self.crossover.name(),
+            self.mutation.name(),
+            self.seed
+        )
+    }
+
+    pub fn solve(&self, board: &Board) -> Option<Board> {
+        self.solve_with_stats(board).0
+    }
+
+    pub fn solve_with_stats(&self, board: &Board) -> (Option<Board>, SolveStats) {
+        let (outcome, stats) = self.solve_within(board, &Budget::new());
+        (outcome.solution(), stats)
+    }
+
+    /// evaluated individuals count as nodes and generations as the depth
+    pub fn solve_within(&self, board: &Board, budget: &Budget) -> (SolveOutcome, SolveStats) {
+        let (outcome, stats, _) = self.solve_traced(board, budget);
+        (outcome, stats)
+    }
+
+    pub fn solve_traced(
+        &self,
+        board: &Board,
+        budget: &Budget,
+    ) -> (SolveOutcome, SolveStats, Evolution) {
+        let mut stats = SolveStats::new(&self.name());
+        let mut evolution = Evolution::default();
+        let check = budget.start();
+        let mut rng = StdRng::seed_from_u64(self.seed);
+
+        let rules = BoxRules::new(board);
+        if rules.has_clash() {
+            // crossover and mutation only move the free cells, a box repeating a given stays
+            // broken in every individual
+            return (SolveOutcome::Unsolvable, stats, evolution);
+        }
+        let elitism = self.elitism.min(self.population);
+        let mut found = None;
+        stats.time("evolve", |stats| {
+            // (cost, grid) sorted best first
+            let mut population = vec![];
+            let mut best = usize::MAX;
+            let mut last_improvement = 0;
+
+            for generation in 0..self.generations {
+                if generation - last_improvement > self.restart_after {
+                    population.truncate(elitism);
+                    best = population.first().map_or(usize::MAX, |(cost, _)| *cost);
+                    last_improvement = generation;
+                    evolution.restarts.push(generation);
+                }
+                let costs = population.iter().map(|(c, _)| *c).collect::<Vec<_>>();
+                let mut next = population[..elitism.min(population.len())].to_vec();
+                while next.len() < self.population {
+                    if check.exceeded() {
+                        return;
+                    }
+                    stats.nodes += 1;
+                    let grid = if population.len() < self.population {
+                        // the first generation and fresh blood after a restart
+                        rules.random_grid(&mut rng)
+                    } else {
+                        let a = &population[self.selection.pick(&costs, &mut rng)].1;
+                        let b = &population[self.selection.pick(&costs, &mut rng)].1;
+                        let mut child = self.crossover.cross(&rules, a, b, &mut rng);
+                        if rng.gen_bool(self.mutation_rate) {
+                            self.mutation.mutate(&rules, &mut child, &mut rng);
+                        }
+                        child
+                    };
+                    next.push((rules.cost(&grid), grid));
+                }
+                next.sort_by_key(|(cost, _)| *cost);
+                population = next;
+                stats.max_depth = generation + 1;
+
+                let costs = population.iter().map(|(c, _)| *c);
+                evolution.generations.push(GenerationStats {
+                    generation,
+                    best: population[0].0,
+                    mean: costs.clone().sum::<usize>() as f64 / population.len() as f64,
+                    worst: costs.max().unwrap(),
+                });
+                if population[0].0 < best {
+                    best = population[0].0;
+                    last_improvement = generation;
+                }
+                if best == 0 {
+                    found = Some(rules.to_board(board, &population[0].1));
+                    return;
+                }
+            }
+        });
+
+        stats.estimated_memory = (2 * self.population + 1) * board_bytes(board);
+        if let Some(solution) = found {
+            stats.solutions = 1;
+            return (SolveOutcome::Solved(solution), stats, evolution);
+        }
+        // the last generation still had a cost, reported like a spent node budget
+        stats.gave_up = Some(check.hit().unwrap_or(Limit::Nodes));
+        (
+            SolveOutcome::GaveUp(stats.gave_up.unwrap()),
+            stats,
+            evolution,
+        )
+    }
+}
+
+impl Evolution {
+    /// `generation,best,mean,worst` lines with a header
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("generation,best,mean,worst\n");
+        for g in &self.generations {
+            writeln!(out, "{},{},{},{}", g.generation, g.best, g.mean, g.worst).unwrap();
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::board::Board;
+    use crate::solvers::backtracking::Backtracking;
+    use crate::solvers::budget::{Budget, Limit, SolveOutcome};
+    use crate::solvers::genetic::{Crossover, Genetic, Mutation, Selection};
+
+    #[test]
+    fn check_genetic() {
+        let board = Board::from_archive(0).unwrap();
+        let solver = Genetic::new(7);
+        let (outcome, stats, evolution) = solver.solve_traced(&board, &Budget::new());
+        let solution = outcome.solution().unwrap();
+        assert!(solution.is_finished());
+        assert_eq!(stats.max_depth, evolution.generations.len());
+        assert!(evolution
+            .to_csv()
+            .starts_with("generation,best,mean,worst\n"));
+        // seeded parents, cuts and mutations breed the same generations
+        assert_eq!(solver.solve(&board), Some(solution));
+
+        // the fittest individual of a killer puzzle has every cage at its clue too
+        let killer = Board::sample_with_rows(6);
+        let solution = solver.solve(&killer).unwrap();
+        assert_eq!(solution.cage_error(), 0);
+        assert_eq!(Some(solution), Backtracking::new().solve(&killer));
+
+        // every operator runs, a few generations aren't enough to solve it
+        for selection in Selection::ALL {
+            for crossover in Crossover::ALL {
+                for mutation in Mutation::ALL {
+                    let solver = Genetic::new(7)
+                        .with_generations(3)
+                        .with_selection(selection)
+                        .with_crossover(crossover)
+                        .with_mutation(mutation);
+                    let (outcome, _) = solver.solve_within(&board, &Budget::new());
+                    assert_eq!(outcome, SolveOutcome::GaveUp(Limit::Nodes));
+                }
+            }
+        }
+    }
+}
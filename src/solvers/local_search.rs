@@ -0,0 +1,301 @@
+use crate::data::board::Board;
+use crate::solvers::box_filling::{swap, BoxRules, Cell, Grid};
+use crate::solvers::budget::{Budget, Limit, SolveOutcome};
+use crate::solvers::stats::{board_bytes, SolveStats};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fmt::Write;
+
+/// Stochastic solver: every box is filled with a permutation of its missing digits, then cells
+/// are swapped inside boxes to bring the broken rules down to zero.
+///
+/// Boxes stay valid the whole time, the cost counts repeated digits in rows and columns, how
+/// far cages are from their clue and cells that break a variant. It can't prove a puzzle has
+/// no solution, running out of steps or restarts gives up.
+#[derive(Debug, Clone)]
+pub struct LocalSearch {
+    method: Method,
+    seed: u64,
+    max_steps: usize,
+    /// steps without a new best cost before starting over from a fresh random filling
+    restart_after: usize,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Method {
+    /// accepts a worse swap with probability `exp(-delta / temperature)`, the temperature is
+    /// multiplied by `cooling` every `size * size` steps
+    Annealing { cooling: f64 },
+    /// takes the best of `candidates` random swaps that doesn't move a cell swapped in the last
+    /// `tenure` steps, unless it beats the best cost so far
+    Tabu { tenure: usize, candidates: usize },
+}
+
+/// The cost over time of one run, for plotting convergence.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Trace {
+    pub points: Vec<TracePoint>,
+    /// steps at which the search started over
+    pub restarts: Vec<usize>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TracePoint {
+    pub step: usize,
+    pub cost: usize,
+    pub best: usize,
+    /// 0 for tabu search
+    pub temperature: f64,
+}
+
+/// steps between two points of the trace
+const TRACE_EVERY: usize = 100;
+
+impl Default for LocalSearch {
+    fn default() -> Self {
+        Self::annealing(0)
+    }
+}
+
+impl LocalSearch {
+    pub fn annealing(seed: u64) -> Self {
+        Self {
+            method: Method::Annealing { cooling: 0.99 },
+            seed,
+            max_steps: 2_000_000,
+            restart_after: 100_000,
+        }
+    }
+    pub fn tabu(seed: u64) -> Self {
+        Self {
+            method: Method::Tabu {
+                tenure: 3,
+                candidates: 60,
+            },
+            seed,
+            max_steps: 200_000,
+            restart_after: 3_000,
+        }
+    }
+    pub fn with_method(mut self, method: Method) -> Self {
+        self.method = method;
+        self
+    }
+    pub fn with_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+    pub fn with_restart_after(mut self, steps: usize) -> Self {
+        self.restart_after = steps;
+        self
+    }
+
+    pub fn name(&self) -> String {
+        match self.method {
+            Method::Annealing { .. } => format!("simulated annealing (seed {})", self.seed),
+            Method::Tabu { .. } => format!("tabu search (seed {})", self.seed),
+        }
+    }
+
+    pub fn solve(&self, board: &Board) -> Option<Board> {
+        self.solve_with_stats(board).0
+    }
+
+    pub fn solve_with_stats(&self, board: &Board) -> (Option<Board>, SolveStats) {
+        let (outcome, stats) = self.solve_within(board, &Budget::new());
+        (outcome.solution(), stats)
+    }
+
+    /// steps count as nodes and rejected swaps as backtracks
+    pub fn solve_within(&self, board: &Board, budget: &Budget) -> (SolveOutcome, SolveStats) {
+        let (outcome, stats, _) = self.solve_traced(board, budget);
+        (outcome, stats)
+    }
+
+    pub fn solve_traced(
+        &self,
+        board: &Board,
+        budget: &Budget,
+    ) -> (SolveOutcome, SolveStats, Trace) {
+        let mut stats = SolveStats::new(&self.name());
+        let mut trace = Trace::default();
+        let check = budget.start();
+        let mut rng = StdRng::seed_from_u64(self.seed);
+
+        let rules = BoxRules::new(board);
+        if rules.has_clash() {
+            // two givens in one box share a digit, no filling can fix that
+            return (SolveOutcome::Unsolvable, stats, trace);
+        }
+        let mut grid = Grid::new();
+        let solved = stats.time("search", |stats| {
+            let grid = &mut grid;
+            *grid = rules.random_grid(&mut rng);
+            let mut cost = rules.cost(grid);
+            let mut best = cost;
+            let mut last_improvement = 0;
+            let mut temperature = self.start_temperature(&rules, grid, &mut rng);
+            let mut tabu: Vec<((usize, usize), usize)> = vec![];
+
+            for step in 0..self.max_steps {
+                if cost == 0 {
+                    return true;
+                }
+                if check.exceeded() {
+                    return false;
+                }
+                stats.nodes += 1;
+                if step - last_improvement > self.restart_after {
+                    *grid = rules.random_grid(&mut rng);
+                    cost = rules.cost(grid);
+                    best = cost;
+                    last_improvement = step;
+                    temperature = self.start_temperature(&rules, grid, &mut rng);
+                    tabu.clear();
+                    trace.restarts.push(step);
+                }
+
+                match self.method {
+                    Method::Annealing { cooling } => {
+                        let (a, b) = match rules.random_swap(&mut rng) {
+                            Some(swap) => swap,
+                            None => break,
+                        };
+                        let delta = rules.swap_delta(grid, a, b);
+                        if delta <= 0 || rng.gen::<f64>() < (-delta as f64 / temperature).exp() {
+                            swap(grid, a, b);
+                            cost = (cost as i64 + delta) as usize;
+                        } else {
+                            stats.backtracks += 1;
+                        }
+                        if step % (rules.size * rules.size) == 0 {
+                            temperature = (temperature * cooling).max(f64::MIN_POSITIVE);
+                        }
+                    }
+                    Method::Tabu { tenure, candidates } => {
+                        tabu.retain(|(_, until)| *until > step);
+                        let mut chosen: Option<(Cell, Cell, i64)> = None;
+                        for _ in 0..candidates {
+                            let (a, b) = match rules.random_swap(&mut rng) {
+                                Some(swap) => swap,
+                                None => break,
+                            };
+                            let delta = rules.swap_delta(grid, a, b);
+                            let is_tabu = tabu.iter().any(|(cell, _)| *cell == a || *cell == b);
+                            let aspiration = (cost as i64 + delta) < best as i64;
+                            if (!is_tabu || aspiration)
+                                && chosen.as_ref().is_none_or(|(_, _, d)| delta < *d)
+                            {
+                                chosen = Some((a, b, delta));
+                            }
+                        }
+                        match chosen {
+                            Some((a, b, delta)) => {
+                                swap(grid, a, b);
+                                cost = (cost as i64 + delta) as usize;
+                                tabu.push((a, step + tenure));
+                                tabu.push((b, step + tenure));
+                            }
+                            None => stats.backtracks += 1,
+                        }
+                    }
+                }
+
+                if cost < best {
+                    best = cost;
+                    last_improvement = step;
+                }
+                if step % TRACE_EVERY == 0 {
+                    trace.points.push(TracePoint {
+                        step,
+                        cost,
+                        best,
+                        temperature: match self.method {
+                            Method::Annealing { .. } => temperature,
+                            Method::Tabu { .. } => 0.,
+                        },
+                    });
+                }
+            }
+            cost == 0
+        });
+
+        stats.estimated_memory = 2 * board_bytes(board);
+        if solved {
+            stats.solutions = 1;
+            return (
+                SolveOutcome::Solved(rules.to_board(board, &grid)),
+                stats,
+                trace,
+            );
+        }
+        // running out of steps is our own node limit
+        stats.gave_up = Some(check.hit().unwrap_or(Limit::Nodes));
+        (SolveOutcome::GaveUp(stats.gave_up.unwrap()), stats, trace)
+    }
+
+    /// the spread of the cost changes of random swaps, so about half of the worse swaps are
+    /// accepted at the start
+    fn start_temperature(&self, rules: &BoxRules, grid: &mut Grid, rng: &mut StdRng) -> f64 {
+        let mut deltas = vec![];
+        for _ in 0..100 {
+            if let Some((a, b)) = rules.random_swap(rng) {
+                deltas.push(rules.swap_delta(grid, a, b) as f64);
+            }
+        }
+        if deltas.is_empty() {
+            return 1.;
+        }
+        let mean = deltas.iter().sum::<f64>() / deltas.len() as f64;
+        let variance = deltas.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / deltas.len() as f64;
+        variance.sqrt().max(1.)
+    }
+}
+
+impl Trace {
+    /// `step,cost,best,temperature` lines with a header
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("step,cost,best,temperature\n");
+        for p in &self.points {
+            writeln!(out, "{},{},{},{}", p.step, p.cost, p.best, p.temperature).unwrap();
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::board::Board;
+    use crate::solvers::backtracking::Backtracking;
+    use crate::solvers::budget::{Budget, SolveOutcome};
+    use crate::solvers::local_search::LocalSearch;
+
+    #[test]
+    fn check_local_search() {
+        let board = Board::from_archive(0).unwrap();
+        for solver in [LocalSearch::annealing(7), LocalSearch::tabu(7)] {
+            let (outcome, stats, trace) = solver.solve_traced(&board, &Budget::new());
+            let solution = outcome.solution().unwrap();
+            assert!(solution.is_finished());
+            assert_eq!(stats.solutions, 1);
+            assert!(trace.to_csv().starts_with("step,cost,best,temperature\n"));
+            // the same seed walks the same way
+            assert_eq!(solver.solve(&board), Some(solution));
+        }
+
+        // archive puzzles have no cages, on the sample swaps also have to bring the cage sums
+        // down to their clues
+        let killer = Board::sample_with_rows(3);
+        for solver in [LocalSearch::annealing(7), LocalSearch::tabu(7)] {
+            let solution = solver.solve(&killer).unwrap();
+            assert_eq!(solution.cage_error(), 0);
+            assert_eq!(Some(solution), Backtracking::new().solve(&killer));
+        }
+
+        let mut clash = Board::new(4);
+        clash.unchecked_set_num(0, 0, 1);
+        clash.unchecked_set_num(1, 1, 1);
+        let (outcome, _) = LocalSearch::annealing(7).solve_within(&clash, &Budget::new());
+        assert_eq!(outcome, SolveOutcome::Unsolvable);
+    }
+}
@@ -0,0 +1,11 @@
+pub mod backtracking;
+pub mod box_filling;
+pub mod budget;
+pub mod cdcl;
+pub mod deduction;
+pub mod genetic;
+pub mod local_search;
+pub mod parallel;
+pub mod sat;
+pub mod search_tree;
+pub mod stats;
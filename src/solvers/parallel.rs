@@ -0,0 +1,226 @@
+use crate::data::board::Board;
+use crate::data::cage_analysis::{derived_cages, DerivedCage};
+use crate::solvers::backtracking::Backtracking;
+use crate::solvers::budget::{Budget, SolveOutcome};
+use crate::solvers::stats::{board_bytes, search_bytes, SolveStats};
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Runs `Backtracking` on a work-stealing thread pool.
+///
+/// The search tree is expanded breadth-first at the most constrained cells until there are a
+/// few subtrees per thread, the subtrees are then searched in parallel. Subtrees keep the
+/// order the sequential search visits them in, so `solve` returns the same board as
+/// `Backtracking::solve` and `count_solutions` the same count.
+#[derive(Debug, Clone)]
+pub struct Parallel {
+    solver: Backtracking,
+    /// started once, clones of the solver share it
+    pool: Arc<rayon::ThreadPool>,
+}
+
+impl Default for Parallel {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+/// subtrees handed out per thread, more of them balance uneven subtrees better
+const SUBTREES_PER_THREAD: usize = 8;
+
+impl Parallel {
+    /// `threads` of 0 uses one thread per cpu
+    pub fn new(threads: usize) -> Self {
+        Self::with_solver(Backtracking::new(), threads)
+    }
+    /// searches with `solver`'s cell and digit ordering
+    pub fn with_solver(solver: Backtracking, threads: usize) -> Self {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .expect("could not start solver threads");
+        Self {
+            solver,
+            pool: Arc::new(pool),
+        }
+    }
+
+    /// the name of the solver with its orderings, as shown in `SolveStats`
+    pub fn name(&self) -> String {
+        self.solver
+            .name()
+            .replacen("backtracking", "parallel backtracking", 1)
+    }
+
+    /// expands `board` into at least `target` subtrees (unless the tree is smaller), in the
+    /// order a sequential search would visit them. the boards expanded count as nodes, `stop`
+    /// is checked before expanding each one
+    fn split(
+        &self,
+        board: &Board,
+        derived: &[DerivedCage],
+        target: usize,
+        stop: &dyn Fn() -> bool,
+        stats: &mut SolveStats,
+    ) -> Vec<Board> {
+        let mut frontier = vec![board.clone()];
+        while frontier.len() < target {
+            let mut expanded = false;
+            let mut next = vec![];
+            for board in frontier {
+                match self.solver.next_cell(&board, derived) {
+                    Some(((x, y), candidates)) if !candidates.is_empty() && !stop() => {
+                        expanded = true;
+                        stats.nodes += 1;
+                        for num in candidates {
+                            let mut child = board.clone();
+                            child.unchecked_set_num(x, y, num);
+                            next.push(child);
+                        }
+                    }
+                    // dead ends are left to the search so they are pruned as usual
+                    _ => next.push(board),
+                }
+            }
+            frontier = next;
+            if !expanded {
+                break;
+            }
+        }
+        frontier
+    }
+
+    /// the first solution in sequential search order. once a subtree finds a solution, every
+    /// subtree after it is cancelled
+    pub fn solve(&self, board: &Board) -> Option<Board> {
+        self.solve_with_stats(board).0
+    }
+
+    /// like `solve`, also returning what the search did. the counts add up the work of every
+    /// thread, including subtrees that were cancelled
+    pub fn solve_with_stats(&self, board: &Board) -> (Option<Board>, SolveStats) {
+        let (outcome, stats) = self.solve_within(board, &Budget::new());
+        (outcome.solution(), stats)
+    }
+
+    /// like `solve_with_stats`, giving up once `budget` runs out. every thread counts towards
+    /// the same node limit
+    pub fn solve_within(&self, board: &Board, budget: &Budget) -> (SolveOutcome, SolveStats) {
+        let mut stats = SolveStats::new(&self.name());
+        let pool = &self.pool;
+        let threads = pool.current_num_threads();
+        let derived = stats.time("derive cages", |_| derived_cages(board));
+        let check = budget.start();
+        let subtrees = stats.time("split", |stats| {
+            let target = threads * SUBTREES_PER_THREAD;
+            self.split(board, &derived, target, &|| check.exceeded(), stats)
+        });
+        let subtree_count = subtrees.len();
+        let first_found = AtomicUsize::new(usize::MAX);
+
+        let results = stats.time("search", |_| {
+            pool.install(|| {
+                subtrees
+                    .into_par_iter()
+                    .enumerate()
+                    .map(|(i, mut subtree)| {
+                        let mut solution = None;
+                        let mut stats = SolveStats::default();
+                        let depth = placed(board, &subtree);
+                        self.solver.search(
+                            &mut subtree,
+                            depth,
+                            &derived,
+                            &mut |solved| {
+                                solution = Some(solved.clone());
+                                false
+                            },
+                            &|| first_found.load(Ordering::Relaxed) < i || check.exceeded(),
+                            None,
+                            &mut stats,
+                        );
+                        if solution.is_some() {
+                            first_found.fetch_min(i, Ordering::Relaxed);
+                        }
+                        (solution, stats)
+                    })
+                    .collect::<Vec<_>>()
+            })
+        });
+        for (_, subtree_stats) in &results {
+            stats.merge(subtree_stats);
+        }
+        stats.estimated_memory = subtree_count * board_bytes(board)
+            + threads.min(subtree_count) * search_bytes(board, stats.max_depth);
+        stats.gave_up = check.hit();
+        let solution = results.into_iter().find_map(|(solution, _)| solution);
+        (check.outcome(solution), stats)
+    }
+
+    /// counts solutions across all threads, stopping once `limit` are found
+    pub fn count_solutions(&self, board: &Board, limit: usize) -> usize {
+        if limit == 0 {
+            return 0;
+        }
+        let pool = &self.pool;
+        let derived = derived_cages(board);
+        let subtrees = self.split(
+            board,
+            &derived,
+            pool.current_num_threads() * SUBTREES_PER_THREAD,
+            &|| false,
+            &mut SolveStats::default(),
+        );
+        let count = AtomicUsize::new(0);
+
+        pool.install(|| {
+            subtrees.into_par_iter().for_each(|mut subtree| {
+                self.solver.search(
+                    &mut subtree,
+                    0,
+                    &derived,
+                    &mut |_| count.fetch_add(1, Ordering::Relaxed) + 1 < limit,
+                    &|| count.load(Ordering::Relaxed) >= limit,
+                    None,
+                    &mut SolveStats::default(),
+                );
+            })
+        });
+        count.into_inner().min(limit)
+    }
+}
+
+/// digits `subtree` has on top of `board`
+fn placed(board: &Board, subtree: &Board) -> usize {
+    let size = board.get_size();
+    (0..size * size)
+        .filter(|i| board.get_value(i % size, i / size) != subtree.get_value(i % size, i / size))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::board::Board;
+    use crate::solvers::backtracking::Backtracking;
+    use crate::solvers::parallel::Parallel;
+
+    #[test]
+    fn check_same_as_sequential() {
+        let board = Board::sample_with_rows(2);
+        let solution = Backtracking::new().solve(&board).unwrap();
+        assert!(solution.is_finished());
+        assert_eq!(Parallel::new(4).solve(&board), Some(solution));
+        assert_eq!(Parallel::new(4).count_solutions(&board, 2), 1);
+
+        let mut open = Board::new(4);
+        open.unchecked_set_num(0, 0, 1);
+        assert_eq!(
+            Parallel::new(3).solve(&open),
+            Backtracking::new().solve(&open)
+        );
+        let sequential = Backtracking::new().count_solutions(&open, 100);
+        assert_eq!(Parallel::new(3).count_solutions(&open, 100), sequential);
+        assert_eq!(Parallel::new(3).count_solutions(&open, 5), 5);
+    }
+}
@@ -0,0 +1,408 @@
+use crate::data::board::Board;
+use crate::data::cage::Operator;
+use crate::data::cage_table::CageTable;
+use crate::data::variant::Variant;
+use crate::solvers::budget::{Budget, SolveOutcome};
+use crate::solvers::cdcl::{Cdcl, SatResult};
+use crate::solvers::stats::SolveStats;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::mem::size_of;
+
+/// Solves a board by encoding it as CNF for the built-in `Cdcl` solver.
+#[derive(Debug, Default, Clone)]
+pub struct Sat {
+    encoding: CageEncoding,
+}
+
+/// How cage clues are turned into clauses. With repeating cages sums and products always use
+/// partial sums, the other operators forbid every wrong filling one by one.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum CageEncoding {
+    /// one selector per digit combination from the `CageTable`, the cells must use the digits
+    /// of a selected combination
+    #[default]
+    Combinations,
+    /// a chain of "the first i cells add up to s" variables, for sums and products. difference
+    /// and quotient cages still use combinations
+    PartialSums,
+}
+
+/// A formula in conjunctive normal form with DIMACS literals.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Cnf {
+    pub vars: usize,
+    pub clauses: Vec<Vec<i32>>,
+}
+
+impl CageEncoding {
+    pub const ALL: [CageEncoding; 2] = [CageEncoding::Combinations, CageEncoding::PartialSums];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CageEncoding::Combinations => "combinations",
+            CageEncoding::PartialSums => "partial-sums",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|e| e.name() == name).copied()
+    }
+}
+
+impl Cnf {
+    fn new_var(&mut self) -> i32 {
+        self.vars += 1;
+        self.vars as i32
+    }
+    fn add(&mut self, clause: Vec<i32>) {
+        self.clauses.push(clause);
+    }
+
+    pub fn to_dimacs(&self) -> String {
+        let mut out = format!("p cnf {} {}\n", self.vars, self.clauses.len());
+        for clause in &self.clauses {
+            for lit in clause {
+                write!(out, "{} ", lit).unwrap();
+            }
+            out.push_str("0\n");
+        }
+        out
+    }
+    pub fn write(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.to_dimacs())
+    }
+}
+
+/// the variable that is true when (x, y) holds `digit`, these come first in every encoding
+pub fn cell_var(board: &Board, x: usize, y: usize, digit: i32) -> i32 {
+    let size = board.get_size();
+    ((y * size + x) * size) as i32 + digit
+}
+
+/// encodes the rules of `board` and its given digits
+pub fn encode(board: &Board, encoding: CageEncoding) -> Cnf {
+    let size = board.get_size();
+    let digits = 1..=size as i32;
+    let mut cnf = Cnf {
+        vars: size * size * size,
+        clauses: vec![],
+    };
+
+    for y in 0..size {
+        for x in 0..size {
+            // exactly one digit
+            cnf.add(digits.clone().map(|d| cell_var(board, x, y, d)).collect());
+            for a in digits.clone() {
+                for b in a + 1..=size as i32 {
+                    cnf.add(vec![-cell_var(board, x, y, a), -cell_var(board, x, y, b)]);
+                }
+            }
+            if board.get_value(x, y) != 0 {
+                cnf.add(vec![cell_var(board, x, y, board.get_value(x, y))]);
+            }
+            // peers cover the houses, the variants and unique cages
+            for (px, py) in board.get_peers(x, y) {
+                if (py, px) > (y, x) {
+                    for d in digits.clone() {
+                        cnf.add(vec![-cell_var(board, x, y, d), -cell_var(board, px, py, d)]);
+                    }
+                }
+            }
+        }
+    }
+    // every digit somewhere in each row, column and box, implied but it helps propagation
+    let (box_width, box_height) = board.get_box_size();
+    for i in 0..size {
+        let (box_x, box_y) = (
+            (i % (size / box_width)) * box_width,
+            (i / (size / box_width)) * box_height,
+        );
+        for d in digits.clone() {
+            cnf.add((0..size).map(|o| cell_var(board, o, i, d)).collect());
+            cnf.add((0..size).map(|o| cell_var(board, i, o, d)).collect());
+            cnf.add(
+                (0..size)
+                    .map(|o| cell_var(board, box_x + o % box_width, box_y + o / box_width, d))
+                    .collect(),
+            );
+        }
+    }
+
+    let repeating = board.get_variants().contains(&Variant::RepeatingCages);
+    for (_, cells) in board.get_cages() {
+        let (x, y) = cells[0];
+        let (op, clue) = (board.get_operator(x, y), board.get_sum(x, y));
+        if clue == 0 || op == Operator::Unlabelled {
+            continue;
+        }
+        let partial = matches!(op, Operator::Sum | Operator::Product)
+            && (repeating || encoding == CageEncoding::PartialSums);
+        if partial {
+            encode_partial(board, &mut cnf, cells, op, clue);
+        } else if repeating {
+            encode_tuples(board, &mut cnf, cells, op, clue);
+        } else {
+            encode_combinations(board, &mut cnf, cells, op, clue);
+        }
+    }
+    cnf
+}
+
+fn encode_combinations(
+    board: &Board,
+    cnf: &mut Cnf,
+    cells: &[(usize, usize)],
+    op: Operator,
+    clue: i32,
+) {
+    let size = board.get_size() as u32;
+    let combinations = CageTable::lookup(size, op, cells.len() as i32, clue)
+        .map(|item| item.combinations)
+        .unwrap_or_default();
+    let mut selectors = vec![];
+    for combination in combinations {
+        let selector = cnf.new_var();
+        selectors.push(selector);
+        for (x, y) in cells {
+            let mut clause = vec![-selector];
+            clause.extend(
+                combination
+                    .iter()
+                    .map(|d| cell_var(board, *x, *y, *d as i32)),
+            );
+            cnf.add(clause);
+        }
+    }
+    // no combinations leaves an empty clause, the cage can't be filled
+    cnf.add(selectors);
+}
+
+fn encode_partial(board: &Board, cnf: &mut Cnf, cells: &[(usize, usize)], op: Operator, clue: i32) {
+    let size = board.get_size() as i32;
+    let (start, combine): (i32, fn(i32, i32) -> i32) = match op {
+        Operator::Product => (1, |a, b| a * b),
+        _ => (0, |a, b| a + b),
+    };
+    // whether a partial result can still grow into the clue
+    let possible = |value: i32, last: bool| match (op, last) {
+        (_, true) => value == clue,
+        (Operator::Product, false) => clue % value == 0,
+        (_, false) => value <= clue,
+    };
+
+    // state variables of the partial results after the cells so far, `None` before the first
+    let mut states: Option<BTreeMap<i32, i32>> = None;
+    for (i, (x, y)) in cells.iter().enumerate() {
+        let last = i + 1 == cells.len();
+        let before = match &states {
+            Some(states) => states.iter().map(|(v, var)| (*v, Some(*var))).collect(),
+            None => vec![(start, None)],
+        };
+        let mut after = BTreeMap::new();
+        for (value, var) in before {
+            for d in 1..=size {
+                let mut clause = var.map(|v| vec![-v]).unwrap_or_default();
+                clause.push(-cell_var(board, *x, *y, d));
+                let next = combine(value, d);
+                if !possible(next, last) {
+                    // this digit can't follow this partial result
+                    cnf.add(clause);
+                } else if !last {
+                    let state = *after.entry(next).or_insert_with(|| cnf.new_var());
+                    clause.push(state);
+                    cnf.add(clause);
+                }
+            }
+        }
+        states = Some(after);
+    }
+}
+
+/// forbids every filling of the cage that misses the clue, one clause each. only used for
+/// difference and quotient cages with repeated digits, which are two cells in practice
+fn encode_tuples(board: &Board, cnf: &mut Cnf, cells: &[(usize, usize)], op: Operator, clue: i32) {
+    let size = board.get_size() as i32;
+    let mut digits = vec![1; cells.len()];
+    loop {
+        if op.evaluate(&digits) != Some(clue) {
+            cnf.add(
+                cells
+                    .iter()
+                    .zip(&digits)
+                    .map(|((x, y), d)| -cell_var(board, *x, *y, *d))
+                    .collect(),
+            );
+        }
+        // the next tuple, counting in base `size`
+        match digits.iter().position(|d| *d < size) {
+            Some(i) => {
+                digits[i] += 1;
+                digits[..i].iter_mut().for_each(|d| *d = 1);
+            }
+            None => break,
+        }
+    }
+}
+
+/// reads the digits of a model back onto `board`
+pub fn decode(board: &Board, model: &[bool]) -> Board {
+    let size = board.get_size();
+    let mut solved = board.clone();
+    for y in 0..size {
+        for x in 0..size {
+            if let Some(d) =
+                (1..=size as i32).find(|d| model[cell_var(board, x, y, *d) as usize - 1])
+            {
+                solved.unchecked_set_num(x, y, d);
+            }
+        }
+    }
+    solved
+}
+
+impl Sat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_encoding(encoding: CageEncoding) -> Self {
+        Self { encoding }
+    }
+    pub fn name(&self) -> String {
+        format!("sat ({})", self.encoding.name())
+    }
+
+    pub fn solve(&self, board: &Board) -> Option<Board> {
+        self.solve_with_stats(board).0
+    }
+
+    pub fn solve_with_stats(&self, board: &Board) -> (Option<Board>, SolveStats) {
+        let (outcome, stats) = self.solve_within(board, &Budget::new());
+        (outcome.solution(), stats)
+    }
+
+    /// like the other solvers, decisions count as nodes and conflicts as backtracks
+    pub fn solve_within(&self, board: &Board, budget: &Budget) -> (SolveOutcome, SolveStats) {
+        let mut stats = SolveStats::new(&self.name());
+        let cnf = stats.time("encode", |_| encode(board, self.encoding));
+        let check = budget.start();
+        let mut cdcl = Cdcl::new(cnf.vars, &cnf.clauses);
+        let result = stats.time("search", |_| cdcl.solve(&|| check.exceeded()));
+
+        stats.nodes = cdcl.decisions + 1;
+        stats.backtracks = cdcl.conflicts;
+        stats.max_depth = cdcl.max_level;
+        stats
+            .propagations
+            .insert("unit".to_string(), cdcl.propagations);
+        stats.estimated_memory = cnf
+            .clauses
+            .iter()
+            .map(|c| c.len() * size_of::<i32>())
+            .sum::<usize>()
+            + cnf.vars * 2 * size_of::<Vec<usize>>();
+        stats.gave_up = check.hit();
+        let solution = match result {
+            SatResult::Sat(model) => Some(decode(board, &model)),
+            SatResult::Unsat | SatResult::Unknown => None,
+        };
+        stats.solutions = solution.is_some() as usize;
+        (check.outcome(solution), stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::board::Board;
+    use crate::data::cage::{Cage, Operator};
+    use crate::data::variant::Variant;
+    use crate::solvers::backtracking::Backtracking;
+    use crate::solvers::budget::{Budget, SolveOutcome};
+    use crate::solvers::sat::{encode, CageEncoding, Sat};
+
+    /// two solved 4x4 grids that only differ in the cells of `RECTANGLE`
+    const GRIDS: [[[i32; 4]; 4]; 2] = [
+        [[1, 2, 3, 4], [3, 4, 1, 2], [2, 1, 4, 3], [4, 3, 2, 1]],
+        [[3, 2, 1, 4], [1, 4, 3, 2], [2, 1, 4, 3], [4, 3, 2, 1]],
+    ];
+    const RECTANGLE: [(usize, usize); 4] = [(0, 0), (2, 0), (0, 1), (2, 1)];
+
+    /// `grid` given outside `RECTANGLE` and one cage with the clue its digits make under `op`,
+    /// so only the cage tells the two grids apart. `None` if the digits make no clue above 0
+    fn one_cage(
+        grid: &[[i32; 4]; 4],
+        cells: &[(usize, usize)],
+        op: Operator,
+        repeating: bool,
+    ) -> Option<Board> {
+        let mut board = Board::new(4);
+        for (y, row) in grid.iter().enumerate() {
+            for (x, num) in row.iter().enumerate() {
+                if !RECTANGLE.contains(&(x, y)) {
+                    board.unchecked_set_num(x, y, *num);
+                }
+            }
+        }
+        board.mark_givens();
+        let digits = cells.iter().map(|(x, y)| grid[*y][*x]).collect::<Vec<_>>();
+        let sum = op.evaluate(&digits).filter(|clue| *clue > 0)?;
+        let index = board.merge_cage(cells);
+        for (x, y) in cells {
+            board.set_cage(*x, *y, Cage { index, sum, op });
+        }
+        if repeating {
+            board.add_variant(Variant::RepeatingCages);
+        }
+        Some(board)
+    }
+
+    #[test]
+    fn check_sat() {
+        let board = Board::sample_puzzle();
+        let cnf = encode(&board, CageEncoding::Combinations);
+        let dimacs = cnf.to_dimacs();
+        assert!(dimacs.starts_with(&format!("p cnf {} {}\n", cnf.vars, cnf.clauses.len())));
+        assert_eq!(dimacs.lines().count(), cnf.clauses.len() + 1);
+
+        // is_finished doesn't look at the cages, the known solution does
+        for encoding in CageEncoding::ALL {
+            let solution = Sat::with_encoding(encoding).solve(&board).unwrap();
+            assert_eq!(solution.get_grid(), board.get_solution());
+        }
+
+        // every operator through the combinations and the partial sums, and with digits
+        // repeating in the cage through the partial sums and products and forbidden tuples.
+        // an encoding that ignores the cage finds the same grid for both clues
+        let repeated = vec![(0, 1), (0, 0), (1, 0), (2, 0)];
+        let cages = [
+            (vec![(2, 1), (3, 1)], Operator::Sum, false),
+            (vec![(0, 0), (1, 0)], Operator::Product, false),
+            (vec![(2, 0), (3, 0)], Operator::Difference, false),
+            (vec![(1, 1), (2, 1)], Operator::Quotient, false),
+            (repeated.clone(), Operator::Sum, true),
+            (repeated, Operator::Product, true),
+            (vec![(2, 0), (3, 0)], Operator::Difference, true),
+        ];
+        for grid in &GRIDS {
+            for (cells, op, repeating) in &cages {
+                let board = match one_cage(grid, cells, *op, *repeating) {
+                    Some(board) => board,
+                    None => continue,
+                };
+                let expected = Backtracking::new().solutions(&board, 2);
+                assert_eq!(expected.len(), 1);
+                for encoding in CageEncoding::ALL {
+                    let solution = Sat::with_encoding(encoding).solve(&board);
+                    assert_eq!(solution.as_ref(), expected.first());
+                }
+            }
+        }
+
+        let mut stuck = Board::new(4);
+        stuck.unchecked_set_num(0, 0, 1);
+        stuck.unchecked_set_num(1, 0, 2);
+        stuck.unchecked_set_num(2, 1, 3);
+        stuck.unchecked_set_num(2, 2, 4);
+        let (outcome, _) = Sat::new().solve_within(&stuck, &Budget::new());
+        assert_eq!(outcome, SolveOutcome::Unsolvable);
+    }
+}
@@ -0,0 +1,235 @@
+use crate::data::board::Board;
+use crate::data::node::Node;
+use serde::Serialize;
+
+/// A recorded backtracking search.
+///
+/// Only the puzzle is stored as a full `Board`, every node keeps the single digit placed to
+/// reach it, `board_at` replays those to rebuild the board anywhere in the tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchTree {
+    pub puzzle: Board,
+    pub root: Node<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Step {
+    /// the digit placed to reach this node, `None` for the root
+    pub decision: Option<Decision>,
+    pub depth: usize,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+pub struct Decision {
+    pub x: usize,
+    pub y: usize,
+    pub digit: i32,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+pub enum Outcome {
+    /// the search branched on this cell, the children are the digits tried
+    Branched {
+        x: usize,
+        y: usize,
+    },
+    Solution,
+    Pruned(PruneReason),
+    /// the search was stopped before this node was finished
+    Cancelled,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+pub enum PruneReason {
+    /// no digit fits this cell any more
+    NoCandidates { x: usize, y: usize },
+    /// the board is full but breaks a rule that is only checked at the end
+    BrokenRule,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+pub struct TreeStats {
+    pub nodes: usize,
+    pub max_depth: usize,
+    /// average number of children of the nodes that branched
+    pub branching_factor: f64,
+    pub solutions: usize,
+    pub pruned: usize,
+}
+
+impl Step {
+    pub(crate) fn root() -> Self {
+        Self {
+            decision: None,
+            depth: 0,
+            outcome: Outcome::Cancelled,
+        }
+    }
+    pub(crate) fn child(&self, x: usize, y: usize, digit: i32) -> Self {
+        Self {
+            decision: Some(Decision { x, y, digit }),
+            depth: self.depth + 1,
+            outcome: Outcome::Cancelled,
+        }
+    }
+}
+
+impl SearchTree {
+    pub fn new(puzzle: Board) -> Self {
+        Self {
+            puzzle,
+            root: Node::new(Step::root()),
+        }
+    }
+
+    /// the board at the node reached by following `path` (child indices) from the root
+    pub fn board_at(&self, path: &[usize]) -> Option<Board> {
+        let mut board = self.puzzle.clone();
+        let mut node = &self.root;
+        for i in path {
+            node = node.children.get(*i)?;
+            if let Some(Step {
+                decision: Some(d), ..
+            }) = node.get_data()
+            {
+                board.unchecked_set_num(d.x, d.y, d.digit);
+            }
+        }
+        Some(board)
+    }
+
+    pub fn stats(&self) -> TreeStats {
+        let steps = self
+            .root
+            .iter()
+            .filter_map(|n| n.get_data().as_ref().map(|step| (n, step)))
+            .collect::<Vec<_>>();
+        let branched = steps
+            .iter()
+            .filter(|(_, step)| matches!(step.outcome, Outcome::Branched { .. }))
+            .collect::<Vec<_>>();
+        let children = branched
+            .iter()
+            .map(|(n, _)| n.children.len())
+            .sum::<usize>();
+        TreeStats {
+            nodes: steps.len(),
+            max_depth: steps.iter().map(|(_, s)| s.depth).max().unwrap_or(0),
+            branching_factor: if branched.is_empty() {
+                0.
+            } else {
+                children as f64 / branched.len() as f64
+            },
+            solutions: steps
+                .iter()
+                .filter(|(_, s)| s.outcome == Outcome::Solution)
+                .count(),
+            pruned: steps
+                .iter()
+                .filter(|(_, s)| matches!(s.outcome, Outcome::Pruned(_)))
+                .count(),
+        }
+    }
+
+    /// Graphviz graph of the tree, solutions are green and pruned nodes red
+    pub fn to_dot(&self) -> String {
+        let mut out =
+            String::from("digraph search {\n    node [shape=box, fontname=\"monospace\"];\n");
+        let mut next_id = 0;
+        write_dot(&self.root, &mut next_id, &mut out);
+        out.push_str("}\n");
+        out
+    }
+
+    /// the puzzle (in the puzzle format), the stats and the tree
+    pub fn to_json(&self) -> String {
+        #[derive(Serialize)]
+        struct Export<'a> {
+            puzzle: String,
+            stats: TreeStats,
+            root: &'a Node<Step>,
+        }
+        serde_json::to_string(&Export {
+            puzzle: self.puzzle.serialize(),
+            stats: self.stats(),
+            root: &self.root,
+        })
+        .expect("search trees always serialize")
+    }
+}
+
+/// writes `node` and its subtree, returns the id it was given
+fn write_dot(node: &Node<Step>, next_id: &mut usize, out: &mut String) -> usize {
+    let id = *next_id;
+    *next_id += 1;
+    if let Some(step) = node.get_data() {
+        let label = match step.decision {
+            Some(d) => format!("r{}c{}={}", d.y + 1, d.x + 1, d.digit),
+            None => "root".to_string(),
+        };
+        let color = match step.outcome {
+            Outcome::Branched { .. } => "black",
+            Outcome::Solution => "green",
+            Outcome::Pruned(_) => "red",
+            Outcome::Cancelled => "gray",
+        };
+        out.push_str(&format!(
+            "    n{} [label=\"{}\", color={}];\n",
+            id, label, color
+        ));
+    }
+    for child in &node.children {
+        let child_id = write_dot(child, next_id, out);
+        out.push_str(&format!("    n{} -> n{};\n", id, child_id));
+    }
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::board::Board;
+    use crate::solvers::backtracking::Backtracking;
+    use crate::solvers::budget::{Budget, Limit, SolveOutcome};
+    use crate::solvers::search_tree::Outcome;
+
+    #[test]
+    fn check_recorded_tree() {
+        let mut board = Board::new(4);
+        board.unchecked_set_num(0, 0, 1);
+        let (solution, tree) = Backtracking::new().solve_recorded(&board);
+        assert_eq!(solution, Backtracking::new().solve(&board));
+
+        let stats = tree.stats();
+        assert_eq!(stats.solutions, 1);
+        assert_eq!(stats.max_depth, 15);
+        assert!(stats.nodes > stats.max_depth);
+
+        // following the last child at every level leads to the solution
+        let mut path = vec![];
+        let mut node = &tree.root;
+        while let Some(last) = node.children.last() {
+            path.push(node.children.len() - 1);
+            node = last;
+        }
+        assert_eq!(node.get_data().as_ref().unwrap().outcome, Outcome::Solution);
+        assert_eq!(tree.board_at(&path), solution);
+        assert_eq!(tree.board_at(&[]), Some(board));
+
+        assert!(tree.to_dot().starts_with("digraph search {"));
+        assert!(tree.to_dot().contains("color=green"));
+        assert!(tree.to_json().contains("\"solutions\":1"));
+    }
+
+    #[test]
+    fn check_recorded_within() {
+        // an empty grid needs more than one node, the tree stops where the search did
+        let budget = Budget::new().with_nodes(1);
+        let (outcome, stats, tree) =
+            Backtracking::new().solve_recorded_within(&Board::new(9), &budget);
+        assert_eq!(outcome, SolveOutcome::GaveUp(Limit::Nodes));
+        assert_eq!(stats.gave_up, Some(Limit::Nodes));
+        assert_eq!(tree.stats().solutions, 0);
+        assert!(tree.stats().nodes <= 3);
+    }
+}
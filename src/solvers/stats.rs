@@ -0,0 +1,168 @@
+use crate::data::board::Board;
+use crate::data::cage::Cage;
+use crate::solvers::budget::Limit;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::mem::size_of;
+use std::time::{Duration, Instant};
+
+/// What a solver did to find its answer, for comparing algorithms.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct SolveStats {
+    /// which solver, with its settings
+    pub solver: String,
+    pub solutions: usize,
+    /// every board the search looked at, including the one it started from
+    pub nodes: usize,
+    /// digits taken back to try the next one
+    pub backtracks: usize,
+    /// nodes where the next cell had a single candidate, so the search went on without a choice
+    pub forced: usize,
+    /// digits placed without a choice, by the technique that found them
+    pub propagations: BTreeMap<String, usize>,
+    /// the most digits placed on top of the puzzle at once
+    pub max_depth: usize,
+    pub phases: Vec<Phase>,
+    /// bytes the search held at once, worked out from the boards and candidates it keeps
+    /// rather than measured
+    pub estimated_memory: usize,
+    /// the budget limit that stopped the search early
+    pub gave_up: Option<Limit>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Phase {
+    pub name: String,
+    pub seconds: f64,
+}
+
+impl SolveStats {
+    pub fn new(solver: &str) -> Self {
+        Self {
+            solver: solver.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// runs `f`, adding the time it took as a phase
+    pub fn time<R>(&mut self, phase: &str, f: impl FnOnce(&mut Self) -> R) -> R {
+        let start = Instant::now();
+        let result = f(self);
+        self.add_phase(phase, start.elapsed());
+        result
+    }
+    pub fn add_phase(&mut self, phase: &str, time: Duration) {
+        match self.phases.iter_mut().find(|p| p.name == phase) {
+            Some(p) => p.seconds += time.as_secs_f64(),
+            None => self.phases.push(Phase {
+                name: phase.to_string(),
+                seconds: time.as_secs_f64(),
+            }),
+        }
+    }
+
+    /// adds the counts of a search that ran next to this one, like the subtrees of `Parallel`.
+    /// phases and memory are left alone, those are measured over the whole run
+    pub fn merge(&mut self, other: &SolveStats) {
+        self.solutions += other.solutions;
+        self.nodes += other.nodes;
+        self.backtracks += other.backtracks;
+        self.forced += other.forced;
+        for (technique, count) in &other.propagations {
+            *self.propagations.entry(technique.clone()).or_insert(0) += count;
+        }
+        self.max_depth = self.max_depth.max(other.max_depth);
+    }
+
+    pub fn total_seconds(&self) -> f64 {
+        self.phases.iter().map(|p| p.seconds).sum()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("stats always serialize")
+    }
+}
+
+/// bytes of one board, its grids included
+pub fn board_bytes(board: &Board) -> usize {
+    let size = board.get_size();
+    let cells = size * size;
+    size_of::<Board>()
+        + 3 * size * size_of::<Vec<i32>>()
+        + cells * (2 * size_of::<i32>() + size_of::<Cage>())
+}
+
+/// bytes a depth-first search down to `depth` holds: the board it works on and the
+/// candidates of every cell on the way down
+pub fn search_bytes(board: &Board, depth: usize) -> usize {
+    board_bytes(board) + depth * (board.get_size() * size_of::<i32>() + size_of::<Vec<i32>>())
+}
+
+impl fmt::Display for SolveStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<16}{}", "solver", self.solver)?;
+        writeln!(f, "{:<16}{}", "solutions", self.solutions)?;
+        if let Some(limit) = self.gave_up {
+            writeln!(f, "{:<16}{:?} limit", "gave up", limit)?;
+        }
+        writeln!(f, "{:<16}{}", "nodes", self.nodes)?;
+        writeln!(f, "{:<16}{}", "backtracks", self.backtracks)?;
+        if self.forced > 0 {
+            writeln!(f, "{:<16}{}", "forced", self.forced)?;
+        }
+        writeln!(f, "{:<16}{}", "max depth", self.max_depth)?;
+        writeln!(
+            f,
+            "{:<16}{:.1} KiB",
+            "est. memory",
+            self.estimated_memory as f64 / 1024.
+        )?;
+        if !self.propagations.is_empty() {
+            writeln!(f, "propagations")?;
+            for (technique, count) in &self.propagations {
+                writeln!(f, "  {:<14}{}", technique, count)?;
+            }
+        }
+        writeln!(f, "time")?;
+        for phase in &self.phases {
+            writeln!(f, "  {:<14}{:.3}s", phase.name, phase.seconds)?;
+        }
+        write!(f, "  {:<14}{:.3}s", "total", self.total_seconds())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::board::Board;
+    use crate::solvers::backtracking::Backtracking;
+    use crate::solvers::parallel::Parallel;
+
+    #[test]
+    fn check_stats() {
+        let mut board = Board::new(4);
+        board.unchecked_set_num(0, 0, 1);
+        let solver = Backtracking::new();
+        let (solution, stats) = solver.solve_with_stats(&board);
+        let tree = solver.solve_recorded(&board).1.stats();
+        assert!(solution.is_some());
+        assert_eq!(stats.solutions, 1);
+        assert_eq!(stats.nodes, tree.nodes);
+        assert_eq!(stats.max_depth, tree.max_depth);
+        let phases = stats
+            .phases
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(phases, ["derive cages", "search"]);
+        assert!(stats.forced > 0);
+        assert!(stats.propagations.is_empty());
+        assert!(stats.to_string().contains("backtracking (mrv + ascending)"));
+        assert!(stats.to_json().contains("\"nodes\": "));
+
+        let (parallel_solution, parallel) = Parallel::new(2).solve_with_stats(&board);
+        assert_eq!(parallel_solution, solution);
+        assert!(parallel.solutions >= 1);
+        assert_eq!(parallel.max_depth, 15);
+    }
+}
@@ -1 +1,231 @@
+use crate::data::board::Board;
+use crate::data::board::Move;
+use crate::data::game::Game;
+use crate::plugins::board_plugin::{Cursor, Editor};
+use crate::plugins::AppState;
+use crate::solvers::backtracking::Backtracking;
+use crate::solvers::budget::{Budget, SolveOutcome};
+use crate::solvers::deduction::explain_hint;
+use bevy::prelude::*;
+use std::time::Duration;
 
+const DIGIT_KEYS: [(KeyCode, KeyCode); 9] = [
+    (KeyCode::Key1, KeyCode::Numpad1),
+    (KeyCode::Key2, KeyCode::Numpad2),
+    (KeyCode::Key3, KeyCode::Numpad3),
+    (KeyCode::Key4, KeyCode::Numpad4),
+    (KeyCode::Key5, KeyCode::Numpad5),
+    (KeyCode::Key6, KeyCode::Numpad6),
+    (KeyCode::Key7, KeyCode::Numpad7),
+    (KeyCode::Key8, KeyCode::Numpad8),
+    (KeyCode::Key9, KeyCode::Numpad9),
+];
+
+/// digits above 9 (16x16 grids) are typed as `A` (10) up to `G` (16)
+const HIGH_DIGIT_KEYS: [KeyCode; 7] = [
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+];
+
+/// how long a hint may search, the solver runs on the frame so it has to stay short
+const HINT_TIME: Duration = Duration::from_millis(250);
+
+/// how long test-solving a cage layout may search
+const TEST_SOLVE_TIME: Duration = Duration::from_secs(2);
+
+/// arrows move the cursor, digits fill (or note) the selected cell, backspace clears it,
+/// `N` toggles notes mode, `H` fills in the selected cell, `Z` undoes, `R` starts over from the
+/// givens and escape goes back to the puzzle picker
+pub fn handle_board_input(
+    keys: Res<Input<KeyCode>>,
+    mut game: ResMut<Game>,
+    mut cursor: ResMut<Cursor>,
+    mut state: ResMut<State<AppState>>,
+) {
+    let size = game.get_board().get_size();
+    if keys.just_pressed(KeyCode::Left) && cursor.x > 0 {
+        cursor.x -= 1;
+    }
+    if keys.just_pressed(KeyCode::Right) && cursor.x + 1 < size {
+        cursor.x += 1;
+    }
+    if keys.just_pressed(KeyCode::Up) && cursor.y > 0 {
+        cursor.y -= 1;
+    }
+    if keys.just_pressed(KeyCode::Down) && cursor.y + 1 < size {
+        cursor.y += 1;
+    }
+    if keys.just_pressed(KeyCode::N) {
+        cursor.notes_mode = !cursor.notes_mode;
+    }
+
+    let (x, y) = (cursor.x, cursor.y);
+    let pressed = DIGIT_KEYS
+        .iter()
+        .map(|(key, numpad_key)| keys.just_pressed(*key) || keys.just_pressed(*numpad_key))
+        .chain(HIGH_DIGIT_KEYS.iter().map(|key| keys.just_pressed(*key)));
+    for (num, pressed) in pressed.enumerate().take(size) {
+        if pressed {
+            let num = num as i32 + 1;
+            if cursor.notes_mode {
+                game.toggle_note(x, y, num);
+            } else {
+                match game.set_num(x, y, num) {
+                    Move::Given => info!("({}, {}) is a given", x, y),
+                    Move::Conflict => info!("{} breaks a rule at ({}, {})", num, x, y),
+                    _ => {}
+                }
+            }
+        }
+    }
+    if keys.any_just_pressed([KeyCode::Back, KeyCode::Delete, KeyCode::Key0])
+        && game.set_num(x, y, 0) == Move::Given
+    {
+        info!("({}, {}) is a given, it can't be erased", x, y);
+    }
+    if keys.just_pressed(KeyCode::H) {
+        match explain_hint(game.get_board(), x, y, HINT_TIME) {
+            Ok((num, reasons)) => {
+                for reason in &reasons {
+                    info!("hint: {}", reason);
+                }
+                game.set_num(x, y, num);
+            }
+            Err(problem) => info!("no hint, {}", problem),
+        }
+    }
+    if keys.just_pressed(KeyCode::Z) {
+        game.undo();
+    }
+    if keys.just_pressed(KeyCode::R) {
+        game.reset();
+    }
+    if keys.just_pressed(KeyCode::Escape) {
+        state.set(AppState::Picker).unwrap();
+    }
+}
+
+/// dragging with the mouse puts the cells in one cage, merging the cages they touch, digits
+/// type the clue of that cage and backspace takes one off, delete removes the cage, `S` saves
+/// the layout, `T` test-solves it and escape goes back to the puzzle picker
+pub fn handle_editor_input(
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<MouseButton>>,
+    mut windows: ResMut<Windows>,
+    mut editor: ResMut<Editor>,
+    mut state: ResMut<State<AppState>>,
+) {
+    let size = editor.board.get_size();
+    let mut changed = false;
+    if buttons.pressed(MouseButton::Left) {
+        if let Some(cell) = hovered_cell(windows.primary(), size) {
+            if !editor.selection.contains(&cell) {
+                editor.selection.push(cell);
+            }
+        }
+    }
+    if buttons.just_released(MouseButton::Left) && !editor.selection.is_empty() {
+        let cells = std::mem::take(&mut editor.selection);
+        editor.cage = editor.board.merge_cage(&cells);
+        changed = true;
+    }
+
+    let cage = editor.cage;
+    let first = editor
+        .board
+        .get_cages()
+        .find(|(index, _)| *index == cage)
+        .map(|(_, cells)| cells[0]);
+    if let Some((x, y)) = first {
+        let sum = editor.board.get_sum(x, y);
+        let typed = [(KeyCode::Key0, KeyCode::Numpad0)]
+            .iter()
+            .chain(DIGIT_KEYS.iter())
+            .position(|(key, numpad_key)| {
+                keys.just_pressed(*key) || keys.just_pressed(*numpad_key)
+            });
+        if let Some(digit) = typed.filter(|_| sum < 1000) {
+            editor.board.set_cage_sum(cage, sum * 10 + digit as i32);
+            changed = true;
+        }
+        if keys.just_pressed(KeyCode::Back) {
+            editor.board.set_cage_sum(cage, sum / 10);
+            changed = true;
+        }
+        if keys.just_pressed(KeyCode::Delete) {
+            editor.board.remove_cage(cage);
+            editor.cage = 0;
+            changed = true;
+        }
+    }
+    if changed {
+        // digits of an earlier test-solve no longer fit the cages
+        editor.board.reset_to_givens();
+        editor.check();
+        windows.primary_mut().set_title(editor_status(&editor));
+    }
+
+    if keys.just_pressed(KeyCode::S) {
+        if let Err(problem) = editor.board.validate_cages(false) {
+            warn!("saving a layout that isn't done: {}", problem);
+        }
+        match save_puzzle(&editor.board, &editor.path) {
+            Ok(()) => info!("saved to {:?}", editor.path),
+            Err(e) => error!("could not save to {:?}: {}", editor.path, e),
+        }
+    }
+    if keys.just_pressed(KeyCode::T) {
+        editor.board.reset_to_givens();
+        let budget = Budget::new().with_time(TEST_SOLVE_TIME);
+        match Backtracking::new().solve_within(&editor.board, &budget).0 {
+            SolveOutcome::Solved(solution) => editor.board = solution,
+            SolveOutcome::Unsolvable => info!("the cages have no solution"),
+            SolveOutcome::GaveUp(_) => info!("no solution found in {:?}", TEST_SOLVE_TIME),
+        }
+    }
+    if keys.just_pressed(KeyCode::Escape) {
+        state.set(AppState::Picker).unwrap();
+    }
+}
+
+/// the cell under the mouse, the board fills the window
+fn hovered_cell(window: &Window, size: usize) -> Option<(usize, usize)> {
+    let position = window.cursor_position()?;
+    let x = position.x / window.width() * size as f32;
+    let y = (window.height() - position.y) / window.height() * size as f32;
+    if x < 0. || y < 0. {
+        return None;
+    }
+    let (x, y) = (x as usize, y as usize);
+    (x < size && y < size).then_some((x, y))
+}
+
+/// the total of the clues against the total of the grid, and the first cage with a problem
+fn editor_status(editor: &Editor) -> String {
+    let board = &editor.board;
+    let size = board.get_size() as i32;
+    let total = board
+        .get_cages()
+        .map(|(_, cells)| board.get_sum(cells[0].0, cells[0].1))
+        .sum::<i32>();
+    let mut status = format!("clues {} of {}", total, size * size * (size + 1) / 2);
+    if let Some((_, problem)) = editor.problems.first() {
+        status.push_str(&format!(", {}", problem));
+    }
+    status
+}
+
+/// writes the layout without any test-solve digits
+fn save_puzzle(board: &Board, path: &std::path::Path) -> std::io::Result<()> {
+    let mut board = board.clone();
+    board.reset_to_givens();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, board.serialize())
+}
@@ -0,0 +1,213 @@
+use crate::data::board::{Board, CellState};
+use crate::data::game::Game;
+use crossterm::style::Color;
+
+/// characters inside a cell, the clue goes on the first line and the digit or notes on the
+/// second
+pub const CELL_WIDTH: usize = 5;
+pub const CELL_HEIGHT: usize = 2;
+
+/// the background of every other box, cage borders are drawn with lines so boxes are shaded
+pub const SHADE: Color = Color::AnsiValue(236);
+
+/// one character of the drawn board
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Glyph {
+    pub ch: char,
+    pub color: Color,
+    pub bold: bool,
+    /// the cursor is drawn in reverse video
+    pub reverse: bool,
+    pub shaded: bool,
+}
+
+impl Glyph {
+    fn blank() -> Self {
+        Self {
+            ch: ' ',
+            color: Color::Reset,
+            bold: false,
+            reverse: false,
+            shaded: false,
+        }
+    }
+}
+
+/// the board of `game` with its notes, heavy lines run along the cage borders and the clue
+/// sits in the first cell of every cage. cells outside cages get a border of their own
+pub fn draw(game: &Game, cursor: Option<(usize, usize)>) -> Vec<Vec<Glyph>> {
+    let board = game.get_board();
+    let size = board.get_size();
+    let (width, height) = (CELL_WIDTH + 1, CELL_HEIGHT + 1);
+    let mut lines = vec![vec![Glyph::blank(); size * width + 1]; size * height + 1];
+
+    // whether a border runs between two cells, `None` lies outside the board
+    let cage = |x: usize, y: usize, dx: usize, dy: usize| {
+        let (x, y) = (x.checked_sub(dx)?, y.checked_sub(dy)?);
+        (x < size && y < size).then(|| board.get_cage_index(x, y))
+    };
+    let border = |a: Option<usize>, b: Option<usize>| a != b || a == Some(0);
+    for gy in 0..=size {
+        for gx in 0..=size {
+            // the four lines meeting at this corner of the grid
+            let up = gy > 0 && border(cage(gx, gy, 1, 1), cage(gx, gy, 0, 1));
+            let down = gy < size && border(cage(gx, gy, 1, 0), cage(gx, gy, 0, 0));
+            let left = gx > 0 && border(cage(gx, gy, 1, 1), cage(gx, gy, 1, 0));
+            let right = gx < size && border(cage(gx, gy, 0, 1), cage(gx, gy, 0, 0));
+            lines[gy * height][gx * width].ch = corner(up, down, left, right);
+            if right {
+                for glyph in &mut lines[gy * height][gx * width + 1..(gx + 1) * width] {
+                    glyph.ch = '━';
+                }
+            }
+            if down {
+                for line in &mut lines[gy * height + 1..(gy + 1) * height] {
+                    line[gx * width].ch = '┃';
+                }
+            }
+        }
+    }
+
+    for (row, line) in lines.iter_mut().enumerate() {
+        for (column, glyph) in line.iter_mut().enumerate() {
+            glyph.shaded = shaded(
+                board,
+                touching(column, width, size),
+                touching(row, height, size),
+            );
+        }
+    }
+
+    for y in 0..size {
+        for x in 0..size {
+            let (left, top) = (x * width + 1, y * height + 1);
+            let cell = &mut lines[top..top + CELL_HEIGHT];
+            if board.get_cage_cells(x, y).first() == Some(&(x, y)) && board.get_sum(x, y) != 0 {
+                let mut clue = board.get_sum(x, y).to_string();
+                if board.has_operators() {
+                    clue.push_str(board.get_operator(x, y).label());
+                }
+                write(&mut cell[0][left..], &clue, Color::DarkYellow, false);
+            }
+            let num = board.get_value(x, y);
+            if num != 0 {
+                let (color, bold) = match board.get_state(x, y) {
+                    CellState::Player => (Color::Blue, false),
+                    CellState::Solver => (Color::Magenta, false),
+                    _ => (Color::Reset, true),
+                };
+                let text = digit(num).to_string();
+                write(&mut cell[1][left + CELL_WIDTH / 2..], &text, color, bold);
+            } else if !game.get_notes(x, y).is_empty() {
+                let notes = game.get_notes(x, y);
+                let mut text = notes.iter().map(|n| digit(*n)).collect::<String>();
+                if notes.len() > CELL_WIDTH {
+                    text = text.chars().take(CELL_WIDTH - 1).collect();
+                    text.push('+');
+                }
+                write(&mut cell[1][left..], &text, Color::DarkGrey, false);
+            }
+            if cursor == Some((x, y)) {
+                for line in cell {
+                    for glyph in &mut line[left..left + CELL_WIDTH] {
+                        glyph.reverse = true;
+                    }
+                }
+            }
+        }
+    }
+    lines
+}
+
+/// the digit as it is typed, `A` for 10 up to `G` for 16
+pub fn digit(num: i32) -> char {
+    match num {
+        1..=9 => (b'0' + num as u8) as char,
+        _ => (b'A' + (num - 10) as u8) as char,
+    }
+}
+
+/// the cells a column (or row) of characters lies in, both cells next to a line
+fn touching(position: usize, width: usize, size: usize) -> Vec<usize> {
+    let cell = position / width;
+    match position % width {
+        0 => [cell.checked_sub(1), Some(cell)]
+            .into_iter()
+            .flatten()
+            .filter(|c| *c < size)
+            .collect(),
+        _ => vec![cell],
+    }
+}
+
+/// whether all the cells lie in the same box and that box is shaded, like a checkerboard
+fn shaded(board: &Board, xs: Vec<usize>, ys: Vec<usize>) -> bool {
+    let (box_width, box_height) = board.get_box_size();
+    let mut boxes = xs
+        .iter()
+        .flat_map(|x| ys.iter().map(move |y| (x / box_width, y / box_height)));
+    let first = match boxes.next() {
+        Some(first) => first,
+        None => return false,
+    };
+    (first.0 + first.1) % 2 == 1 && boxes.all(|b| b == first)
+}
+
+fn write(glyphs: &mut [Glyph], text: &str, color: Color, bold: bool) {
+    for (glyph, ch) in glyphs.iter_mut().zip(text.chars()) {
+        glyph.ch = ch;
+        glyph.color = color;
+        glyph.bold = bold;
+    }
+}
+
+fn corner(up: bool, down: bool, left: bool, right: bool) -> char {
+    match (up, down, left, right) {
+        (false, false, false, false) => ' ',
+        (true, false, false, false) => '╹',
+        (false, true, false, false) => '╻',
+        (false, false, true, false) => '╸',
+        (false, false, false, true) => '╺',
+        (true, true, false, false) => '┃',
+        (false, false, true, true) => '━',
+        (false, true, false, true) => '┏',
+        (false, true, true, false) => '┓',
+        (true, false, false, true) => '┗',
+        (true, false, true, false) => '┛',
+        (true, true, false, true) => '┣',
+        (true, true, true, false) => '┫',
+        (false, true, true, true) => '┳',
+        (true, false, true, true) => '┻',
+        (true, true, true, true) => '╋',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::board::Board;
+    use crate::data::game::Game;
+    use crate::tui::grid::{draw, CELL_HEIGHT, CELL_WIDTH};
+
+    #[test]
+    fn check_draw() {
+        let mut game = Game::new(Board::sample_puzzle());
+        game.set_num(1, 0, 1);
+        game.toggle_note(2, 0, 4);
+        game.toggle_note(2, 0, 5);
+        let lines = draw(&game, Some((1, 0)));
+        let text = lines
+            .iter()
+            .map(|line| line.iter().map(|g| g.ch).collect::<String>())
+            .collect::<Vec<_>>();
+        assert_eq!(text.len(), 9 * (CELL_HEIGHT + 1) + 1);
+        assert!(text
+            .iter()
+            .all(|line| line.chars().count() == 9 * (CELL_WIDTH + 1) + 1));
+        // the first cage holds the two top left cells, the next one has a clue of 15 and notes
+        assert!(text[0].starts_with("┏━━━━━━━━━━━┳━━━━━"));
+        assert!(text[1].starts_with("┃3          ┃15    "));
+        assert!(text[2].starts_with("┃        1  ┃45    "));
+        assert!(lines[2][8].reverse && !lines[2][14].reverse);
+        assert!(lines[1][1 + 3 * (CELL_WIDTH + 1)].shaded && !lines[1][1].shaded);
+    }
+}
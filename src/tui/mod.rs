@@ -0,0 +1,388 @@
+//! Plays puzzles in a terminal, a lighter alternative to the app for SSH sessions.
+//!
+//! `grid` draws the board into characters, this module runs the screen and the keys. Besides
+//! playing, a backtracking search from the current board can be replayed step by step.
+
+use crate::data::board::{Board, Move};
+use crate::data::game::Game;
+use crate::solvers::backtracking::Backtracking;
+use crate::solvers::budget::Budget;
+use crate::solvers::deduction::explain_hint;
+use crate::solvers::search_tree::{Decision, Outcome, PruneReason, SearchTree, Step};
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::SetForegroundColor;
+use crossterm::style::{Attribute, Color, Print, SetAttribute, SetBackgroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use std::io::{stdout, Write};
+use std::time::Duration;
+
+pub mod grid;
+
+/// how long a hint may search, the screen waits for it
+const HINT_TIME: Duration = Duration::from_millis(500);
+
+/// how long the search of a replay may run, every node it visits is kept as a frame
+const REPLAY_TIME: Duration = Duration::from_secs(2);
+
+/// the time between replay steps to start with, and the bounds `+` and `-` keep it in
+const REPLAY_DELAY: Duration = Duration::from_millis(200);
+const FASTEST_REPLAY: Duration = Duration::from_millis(5);
+const SLOWEST_REPLAY: Duration = Duration::from_secs(2);
+
+const PLAY_KEYS: &str = "arrows move, digits fill, 0 or backspace clears, n notes, h hint, \
+                         u undo, r reset, t replay a solve, q quits";
+const REPLAY_KEYS: &str = "space plays or pauses, arrows step, + and - change the speed, \
+                           home and end jump, esc goes back";
+
+/// one node of a recorded search, in the order the search visited them
+#[derive(Debug, Clone, PartialEq)]
+struct Frame {
+    depth: usize,
+    decision: Option<Decision>,
+    outcome: Outcome,
+}
+
+/// a recorded search being played back
+#[derive(Debug, Clone, PartialEq)]
+struct Replay {
+    puzzle: Board,
+    frames: Vec<Frame>,
+    at: usize,
+    playing: bool,
+    delay: Duration,
+    /// the search ran out of time, so the frames end before a solution
+    gave_up: bool,
+}
+
+/// what the screen shows and the keys act on
+#[derive(Debug, Clone, PartialEq)]
+struct Tui {
+    game: Game,
+    cursor: (usize, usize),
+    notes_mode: bool,
+    message: String,
+    replay: Option<Replay>,
+    quit: bool,
+}
+
+/// leaves the terminal as it was found, also when the game panics
+struct RawMode;
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = execute!(stdout(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// plays `puzzle` until the player quits
+pub fn run(puzzle: Board) -> Result<(), String> {
+    let mut tui = Tui {
+        game: Game::new(puzzle),
+        cursor: (0, 0),
+        notes_mode: false,
+        message: String::new(),
+        replay: None,
+        quit: false,
+    };
+    terminal::enable_raw_mode().map_err(|e| e.to_string())?;
+    let _raw_mode = RawMode;
+    execute!(stdout(), EnterAlternateScreen, Hide).map_err(|e| e.to_string())?;
+
+    while !tui.quit {
+        tui.show().map_err(|e| e.to_string())?;
+        let timeout = match &tui.replay {
+            Some(replay) if replay.playing => replay.delay,
+            _ => Duration::from_secs(60),
+        };
+        if event::poll(timeout).map_err(|e| e.to_string())? {
+            if let Event::Key(key) = event::read().map_err(|e| e.to_string())? {
+                tui.handle_key(key);
+            }
+        } else if let Some(replay) = &mut tui.replay {
+            replay.step(1);
+        }
+    }
+    Ok(())
+}
+
+impl Tui {
+    fn handle_key(&mut self, key: KeyEvent) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+        } else if self.replay.is_some() {
+            self.handle_replay_key(key.code);
+        } else {
+            self.handle_play_key(key.code);
+        }
+    }
+
+    fn handle_play_key(&mut self, code: KeyCode) {
+        let size = self.game.get_board().get_size();
+        let (x, y) = self.cursor;
+        match code {
+            KeyCode::Left if x > 0 => self.cursor.0 -= 1,
+            KeyCode::Right if x + 1 < size => self.cursor.0 += 1,
+            KeyCode::Up if y > 0 => self.cursor.1 -= 1,
+            KeyCode::Down if y + 1 < size => self.cursor.1 += 1,
+            KeyCode::Char('n') => self.notes_mode = !self.notes_mode,
+            KeyCode::Char('h') => {
+                self.message = match explain_hint(self.game.get_board(), x, y, HINT_TIME) {
+                    Ok((num, reasons)) => {
+                        self.game.set_num(x, y, num);
+                        match reasons.last() {
+                            Some(reason) => format!("hint: {}", reason),
+                            None => format!("hint: the solver puts {} here", num),
+                        }
+                    }
+                    Err(problem) => format!("no hint, {}", problem),
+                };
+            }
+            KeyCode::Char('u') | KeyCode::Char('z') => {
+                self.message = match self.game.undo() {
+                    true => String::new(),
+                    false => "nothing to undo".to_string(),
+                };
+            }
+            KeyCode::Char('r') => self.game.reset(),
+            KeyCode::Char('t') => self.start_replay(),
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Backspace | KeyCode::Delete | KeyCode::Char('0') => {
+                self.message = match self.game.set_num(x, y, 0) {
+                    Move::Given => format!("({}, {}) is a given, it can't be erased", x, y),
+                    _ => String::new(),
+                };
+            }
+            KeyCode::Char(c) => {
+                // digits above 9 are typed as `A` (10) up to `G` (16), like in the app
+                let num = match c {
+                    '1'..='9' => c as i32 - '0' as i32,
+                    'A'..='G' => c as i32 - 'A' as i32 + 10,
+                    _ => return,
+                };
+                if num as usize > size {
+                    return;
+                }
+                if self.notes_mode {
+                    self.game.toggle_note(x, y, num);
+                    return;
+                }
+                self.message = match self.game.set_num(x, y, num) {
+                    Move::Given => format!("({}, {}) is a given", x, y),
+                    Move::Conflict => format!("{} breaks a rule at ({}, {})", num, x, y),
+                    _ => String::new(),
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_replay_key(&mut self, code: KeyCode) {
+        let replay = self.replay.as_mut().unwrap();
+        match code {
+            KeyCode::Char(' ') => replay.playing = !replay.playing,
+            KeyCode::Right => replay.step(1),
+            KeyCode::Left => replay.step(-1),
+            KeyCode::Home => replay.at = 0,
+            KeyCode::End => replay.at = replay.frames.len() - 1,
+            KeyCode::Char('+') | KeyCode::Up => {
+                replay.delay = (replay.delay / 2).max(FASTEST_REPLAY);
+            }
+            KeyCode::Char('-') | KeyCode::Down => {
+                replay.delay = (replay.delay * 2).min(SLOWEST_REPLAY);
+            }
+            KeyCode::Esc | KeyCode::Char('q') => self.replay = None,
+            _ => {}
+        }
+    }
+
+    /// records a backtracking search from the current board
+    fn start_replay(&mut self) {
+        let mut puzzle = self.game.get_board().clone();
+        // the replay's digits are the solver's, the player's entries stay givens of the search
+        puzzle.mark_givens();
+        let budget = Budget::new().with_time(REPLAY_TIME);
+        self.replay = Some(Replay::new(puzzle, &budget));
+    }
+
+    fn show(&self) -> crossterm::Result<()> {
+        let (game, cursor, status, keys) = match &self.replay {
+            Some(replay) => {
+                let frame = &replay.frames[replay.at];
+                let cursor = frame.decision.map(|d| (d.x, d.y));
+                let mut status = format!(
+                    "step {} of {}: {}",
+                    replay.at,
+                    replay.frames.len() - 1,
+                    describe(frame)
+                );
+                if replay.gave_up {
+                    status.push_str(&format!(
+                        " | gave up after {}s, the search stops early",
+                        REPLAY_TIME.as_secs()
+                    ));
+                }
+                (Game::new(replay.board()), cursor, status, REPLAY_KEYS)
+            }
+            None => {
+                let mut status = format!("notes {}", if self.notes_mode { "on" } else { "off" });
+                if self.game.is_finished() {
+                    status.push_str(", solved!");
+                }
+                if !self.message.is_empty() {
+                    status.push_str(&format!(" | {}", self.message));
+                }
+                (self.game.clone(), Some(self.cursor), status, PLAY_KEYS)
+            }
+        };
+
+        let mut out = stdout();
+        queue!(out, MoveTo(0, 0), Clear(ClearType::All))?;
+        let lines = grid::draw(&game, cursor);
+        for (row, line) in lines.iter().enumerate() {
+            queue!(out, MoveTo(0, row as u16))?;
+            for glyph in line {
+                queue!(
+                    out,
+                    SetAttribute(Attribute::Reset),
+                    SetForegroundColor(glyph.color),
+                    SetBackgroundColor(if glyph.shaded {
+                        grid::SHADE
+                    } else {
+                        Color::Reset
+                    })
+                )?;
+                if glyph.bold {
+                    queue!(out, SetAttribute(Attribute::Bold))?;
+                }
+                if glyph.reverse {
+                    queue!(out, SetAttribute(Attribute::Reverse))?;
+                }
+                queue!(out, Print(glyph.ch))?;
+            }
+        }
+        queue!(
+            out,
+            SetAttribute(Attribute::Reset),
+            MoveTo(0, lines.len() as u16 + 1),
+            Print(status),
+            MoveTo(0, lines.len() as u16 + 2),
+            Print(keys)
+        )?;
+        out.flush()
+    }
+}
+
+impl Replay {
+    /// records a search of `puzzle` within `budget`, starting to play from the root
+    fn new(puzzle: Board, budget: &Budget) -> Self {
+        let (_, stats, tree) = Backtracking::new().solve_recorded_within(&puzzle, budget);
+        Self {
+            frames: frames(&tree),
+            puzzle,
+            at: 0,
+            playing: true,
+            delay: REPLAY_DELAY,
+            gave_up: stats.gave_up.is_some(),
+        }
+    }
+
+    /// moves `by` frames, stopping at either end
+    fn step(&mut self, by: isize) {
+        let last = self.frames.len() as isize - 1;
+        self.at = (self.at as isize + by).clamp(0, last) as usize;
+        if self.at as isize == last {
+            self.playing = false;
+        }
+    }
+
+    /// the board at the current frame: the puzzle with the decisions of the frame and the
+    /// frames it came from, found by walking back to each smaller depth
+    fn board(&self) -> Board {
+        let mut board = self.puzzle.clone();
+        let mut depth = self.frames[self.at].depth + 1;
+        for frame in self.frames[..=self.at].iter().rev() {
+            if frame.depth < depth {
+                depth = frame.depth;
+                if let Some(d) = frame.decision {
+                    board.unchecked_set_num(d.x, d.y, d.digit);
+                }
+            }
+        }
+        board
+    }
+}
+
+/// every node of the search, depth first like the search visited them
+fn frames(tree: &SearchTree) -> Vec<Frame> {
+    tree.root
+        .iter()
+        .filter_map(|node| node.get_data().as_ref())
+        .map(|step: &Step| Frame {
+            depth: step.depth,
+            decision: step.decision,
+            outcome: step.outcome,
+        })
+        .collect()
+}
+
+fn describe(frame: &Frame) -> String {
+    let mut out = match frame.decision {
+        Some(d) => format!("{} at ({}, {})", grid::digit(d.digit), d.x, d.y),
+        None => "the board to solve".to_string(),
+    };
+    match frame.outcome {
+        Outcome::Branched { x, y } => out.push_str(&format!(", next ({}, {})", x, y)),
+        Outcome::Solution => out.push_str(", solved"),
+        Outcome::Pruned(PruneReason::NoCandidates { x, y }) => {
+            out.push_str(&format!(", nothing fits ({}, {})", x, y))
+        }
+        Outcome::Pruned(PruneReason::BrokenRule) => out.push_str(", breaks a rule"),
+        Outcome::Cancelled => {}
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::board::Board;
+    use crate::solvers::backtracking::Backtracking;
+    use crate::solvers::budget::Budget;
+    use crate::solvers::search_tree::Outcome;
+    use crate::tui::Replay;
+
+    #[test]
+    fn check_replay() {
+        let mut puzzle = Board::sample_puzzle();
+        let solution = Backtracking::new().solve(&puzzle).unwrap();
+        // a few digits in, so the search stays short
+        for y in 0..6 {
+            for x in 0..9 {
+                puzzle.unchecked_set_num(x, y, solution.get_value(x, y));
+            }
+        }
+        puzzle.mark_givens();
+        let mut replay = Replay::new(puzzle.clone(), &Budget::new());
+        assert!(!replay.gave_up);
+        replay.at = replay
+            .frames
+            .iter()
+            .position(|f| f.outcome == Outcome::Solution)
+            .unwrap();
+        assert_eq!(replay.board().get_grid(), solution.clone().get_grid());
+        replay.at = 0;
+        assert_eq!(replay.board(), puzzle);
+        replay.step(-1);
+        assert_eq!(replay.at, 0);
+        replay.step(replay.frames.len() as isize);
+        assert!(!replay.playing);
+
+        // a search cut short still replays the nodes it visited
+        let replay = Replay::new(Board::new(9), &Budget::new().with_nodes(1));
+        assert!(replay.gave_up);
+        assert!(!replay.frames.is_empty());
+        assert!(replay.frames.iter().all(|f| f.outcome != Outcome::Solution));
+    }
+}
@@ -0,0 +1,172 @@
+//! Runs every solver over the puzzles in `assets/corpus`.
+
+use killer_sudoku::data::board::Board;
+use killer_sudoku::data::corpus::{load_corpus, CorpusPuzzle, Solutions};
+use killer_sudoku::solvers::backtracking::Backtracking;
+use killer_sudoku::solvers::budget::{Budget, SolveOutcome};
+use killer_sudoku::solvers::deduction::{deduce, Grade};
+use killer_sudoku::solvers::genetic::Genetic;
+use killer_sudoku::solvers::local_search::LocalSearch;
+use killer_sudoku::solvers::parallel::Parallel;
+use killer_sudoku::solvers::sat::{CageEncoding, Sat};
+
+/// individuals or moves the local solvers get, they can't prove anything so this only checks
+/// that whatever they return is right
+const LOCAL_NODES: usize = 5_000;
+
+/// whether `solution` fills `board`, keeps its givens and meets every cage clue
+fn solves(board: &Board, solution: &Board) -> bool {
+    let size = board.get_size();
+    let cells = (0..size * size).map(|i| (i % size, i / size));
+    solution.is_finished()
+        && cells.clone().all(|(x, y)| {
+            let given = board.get_value(x, y);
+            given == 0 || solution.get_value(x, y) == given
+        })
+        && cells
+            .filter(|(x, y)| board.get_cage_index(*x, *y) != 0)
+            .all(|(x, y)| {
+                let digits = board
+                    .get_cage_cells(x, y)
+                    .iter()
+                    .map(|(cx, cy)| solution.get_value(*cx, *cy))
+                    .collect::<Vec<_>>();
+                board.get_operator(x, y).evaluate(&digits) == Some(board.get_sum(x, y))
+            })
+}
+
+/// checks the outcome of a solver that searches everything
+fn check_complete(entry: &CorpusPuzzle, solver: &str, outcome: SolveOutcome) {
+    match (entry.solutions, outcome) {
+        (Solutions::None, SolveOutcome::Unsolvable) => {}
+        (Solutions::Unique, SolveOutcome::Solved(solution)) => {
+            let expected = entry.board.get_solution().to_vec();
+            assert_eq!(solution.get_grid(), expected, "{} {}", entry.name, solver);
+        }
+        (Solutions::Multiple, SolveOutcome::Solved(solution)) => {
+            assert!(solves(&entry.board, &solution), "{} {}", entry.name, solver);
+        }
+        (solutions, outcome) => panic!(
+            "{} {}: expected {} solutions, got {:?}",
+            entry.name,
+            solver,
+            solutions.name(),
+            outcome
+        ),
+    }
+}
+
+#[test]
+fn check_corpus_files() {
+    // loading checks every file has its solution count and grade
+    let corpus = load_corpus();
+    for grade in Grade::ALL {
+        assert!(
+            corpus.iter().any(|e| e.grade == Some(grade)),
+            "no {} puzzle",
+            grade.name()
+        );
+    }
+    for solutions in Solutions::ALL {
+        assert!(corpus.iter().any(|e| e.solutions == solutions));
+    }
+    for entry in &corpus {
+        if entry.solutions != Solutions::None {
+            let mut solution = entry.board.clone();
+            for (y, row) in entry.board.get_solution().iter().enumerate() {
+                for (x, num) in row.iter().enumerate() {
+                    solution.unchecked_set_num(x, y, *num);
+                }
+            }
+            assert!(solves(&entry.board, &solution), "{}", entry.name);
+            assert_eq!(entry.board.validate_cages(true), Ok(()), "{}", entry.name);
+        }
+    }
+}
+
+#[test]
+fn check_complete_solvers() {
+    let budget = Budget::new();
+    for entry in load_corpus() {
+        let board = &entry.board;
+        let outcome = Backtracking::new().solve_within(board, &budget).0;
+        check_complete(&entry, "backtracking", outcome);
+        let outcome = Parallel::new(2).solve_within(board, &budget).0;
+        check_complete(&entry, "parallel", outcome);
+        for encoding in CageEncoding::ALL {
+            let outcome = Sat::with_encoding(encoding).solve_within(board, &budget).0;
+            check_complete(&entry, encoding.name(), outcome);
+        }
+    }
+}
+
+#[test]
+fn check_solution_counts() {
+    for entry in load_corpus() {
+        let count = match entry.solutions {
+            Solutions::None => 0,
+            Solutions::Unique => 1,
+            _ => 2,
+        };
+        let board = &entry.board;
+        assert_eq!(
+            Backtracking::new().count_solutions(board, 2),
+            count,
+            "{}",
+            entry.name
+        );
+        assert_eq!(
+            Parallel::new(2).count_solutions(board, 2),
+            count,
+            "{}",
+            entry.name
+        );
+    }
+}
+
+#[test]
+fn check_grades() {
+    for entry in load_corpus() {
+        let Some(grade) = entry.grade else { continue };
+        let logic = deduce(&entry.board);
+        assert_eq!(logic.grade(), grade, "{}", entry.name);
+        if logic.solved {
+            assert!(solves(&entry.board, &logic.board), "{}", entry.name);
+        }
+    }
+}
+
+#[test]
+fn check_local_solvers() {
+    let budget = Budget::new().with_nodes(LOCAL_NODES);
+    for entry in load_corpus() {
+        let outcomes = [
+            (
+                "annealing",
+                LocalSearch::annealing(1)
+                    .solve_within(&entry.board, &budget)
+                    .0,
+            ),
+            (
+                "tabu",
+                LocalSearch::tabu(1).solve_within(&entry.board, &budget).0,
+            ),
+            (
+                "genetic",
+                Genetic::new(1).solve_within(&entry.board, &budget).0,
+            ),
+        ];
+        for (name, outcome) in outcomes {
+            match outcome {
+                SolveOutcome::Solved(solution) => {
+                    assert_ne!(entry.solutions, Solutions::None, "{} {}", entry.name, name);
+                    assert!(solves(&entry.board, &solution), "{} {}", entry.name, name);
+                }
+                SolveOutcome::Unsolvable => {
+                    assert_eq!(entry.solutions, Solutions::None, "{} {}", entry.name, name)
+                }
+                SolveOutcome::GaveUp(_) => {}
+            }
+        }
+    }
+}